@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use merkletree::Prover;
+use merkletree::{Prover, Verifier};
 use std::fs;
 
 fn bench_prover_new(c: &mut Criterion) {
@@ -21,5 +21,114 @@ fn bench_prover_new(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_prover_new);
+fn bench_leaf_hashing(c: &mut Criterion) {
+    let content = fs::read_to_string("tests/data/data100000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+
+    let mut group = c.benchmark_group("Prover::new data100000");
+    group.sample_size(10);
+
+    for threads in [1, 2, 4, 8] {
+        group.bench_function(format!("num_threads_{}", threads), |b| {
+            b.iter(|| Prover::new(&data, threads).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_get_proof(c: &mut Criterion) {
+    let content = fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+    let prover = Prover::new(&data, 1).unwrap();
+
+    let mut group = c.benchmark_group("Prover::get_proof");
+    group.sample_size(10);
+
+    group.bench_function("all_leaves", |b| {
+        b.iter(|| {
+            for leaf_index in 0..data.len() {
+                let _ = prover.get_proof(leaf_index).unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_get_proofs(c: &mut Criterion) {
+    let content = fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+    let prover = Prover::new(&data, 1).unwrap();
+    let leaf_indices: Vec<usize> = (0..data.len()).collect();
+
+    let mut group = c.benchmark_group("Prover::get_proofs");
+    group.sample_size(10);
+
+    group.bench_function("sequential_loop", |b| {
+        b.iter(|| {
+            leaf_indices
+                .iter()
+                .map(|&leaf_index| prover.get_proof(leaf_index).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| prover.get_proofs(&leaf_indices).unwrap())
+    });
+
+    group.finish();
+}
+
+fn bench_verify_proof(c: &mut Criterion) {
+    let content = fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+    let prover = Prover::new(&data, 1).unwrap();
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+    let proofs: Vec<_> = (0..data.len())
+        .map(|leaf_index| prover.get_proof(leaf_index).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("Verifier::verify_proof");
+    group.sample_size(10);
+
+    group.bench_function("all_leaves", |b| {
+        b.iter(|| proofs.iter().all(|proof| verifier.verify_proof(proof)))
+    });
+
+    group.finish();
+}
+
+fn bench_verify_batch_dedup(c: &mut Criterion) {
+    let content = fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+    let prover = Prover::new(&data, 1).unwrap();
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+    // Many copies of a few proofs, as returned by a caching layer
+    let proofs: Vec<_> = (0..data.len())
+        .map(|leaf_index| prover.get_proof(leaf_index % 10).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("Verifier::verify_batch repeated proofs");
+    group.sample_size(10);
+
+    group.bench_function("verify_batch", |b| {
+        b.iter(|| verifier.verify_batch(&proofs))
+    });
+    group.bench_function("verify_batch_dedup", |b| {
+        b.iter(|| verifier.verify_batch_dedup(&proofs))
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_prover_new,
+    bench_leaf_hashing,
+    bench_get_proof,
+    bench_get_proofs,
+    bench_verify_proof,
+    bench_verify_batch_dedup
+);
 criterion_main!(benches);
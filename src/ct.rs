@@ -0,0 +1,309 @@
+//! Verification of Certificate Transparency (RFC 6962) inclusion proofs.
+//!
+//! RFC 6962 defines the Merkle Tree Hash of a list of leaves recursively, splitting each list at
+//! the largest power of two smaller than its length. Its audit paths are ordered from the leaf
+//! to the root, and the hashing order at each step depends on both the leaf index and the tree
+//! size. The functions here follow the RFC's algorithm exactly and hash with SHA256 using the
+//! leaf and node prefixes of this crate.
+
+use crate::hasher::hash_node;
+
+/// Verifies an RFC 6962 inclusion proof, allocating nothing beyond a few local variables.
+///
+/// This is the audit path verification algorithm of RFC 9162, section 2.1.3.2, which
+/// reproduces the `MTH`-based verification of RFC 6962.
+///
+/// # Arguments
+///
+/// * `leaf_index` - The zero-based index of the leaf in the tree.
+/// * `tree_size` - The number of leaves in the tree.
+/// * `leaf_hash` - The leaf hash, i.e. the SHA256 hash of `0x00` followed by the leaf data.
+/// * `audit_path` - The sibling hashes ordered from the leaf up to the root.
+/// * `root` - The expected Merkle Tree Hash of the tree.
+///
+/// # Returns
+///
+/// Returns `true` if the audit path leads from the leaf to `root`, otherwise returns `false`.
+pub fn verify_inclusion(
+    leaf_index: usize,
+    tree_size: usize,
+    leaf_hash: [u8; 32],
+    audit_path: &[[u8; 32]],
+    root: [u8; 32],
+) -> bool {
+    if leaf_index >= tree_size {
+        return false;
+    }
+
+    let mut fn_ = leaf_index;
+    let mut sn = tree_size - 1;
+    let mut r = leaf_hash;
+
+    for p in audit_path {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            r = hash_node(p, &r);
+            // Skip the levels where the node is the last one and has no right sibling
+            if fn_ & 1 == 0 {
+                while fn_ & 1 == 0 && fn_ != 0 {
+                    fn_ >>= 1;
+                    sn >>= 1;
+                }
+            }
+        } else {
+            r = hash_node(&r, p);
+        }
+
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && r == root
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::hasher::hash_leaf;
+    use crate::{Prover, Verifier};
+
+    /// Decodes a 64-character hex string into a hash.
+    fn hex(s: &str) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        for (i, byte) in hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        hash
+    }
+
+    /// The leaves of the RFC 6962 test vectors.
+    fn leaves() -> Vec<Vec<u8>> {
+        vec![
+            vec![],
+            vec![0x00],
+            vec![0x10],
+            vec![0x20, 0x21],
+            vec![0x30, 0x31],
+            vec![0x40, 0x41, 0x42, 0x43],
+            (0x50..0x58).collect(),
+            (0x60..0x70).collect(),
+        ]
+    }
+
+    /// The Merkle Tree Hashes of the first `n` test leaves, indexed by `n - 1`.
+    fn roots() -> Vec<[u8; 32]> {
+        [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ]
+        .iter()
+        .map(|s| hex(s))
+        .collect()
+    }
+
+    /// The published inclusion proofs as (leaf_index, tree_size, audit_path).
+    fn inclusion_proofs() -> Vec<(usize, usize, Vec<[u8; 32]>)> {
+        vec![
+            (0, 1, vec![]),
+            (
+                0,
+                8,
+                vec![
+                    hex("96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7"),
+                    hex("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                    hex("6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"),
+                ],
+            ),
+            (
+                5,
+                8,
+                vec![
+                    hex("bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"),
+                    hex("ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0"),
+                    hex("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+                ],
+            ),
+            (
+                2,
+                3,
+                vec![hex(
+                    "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+                )],
+            ),
+            (
+                1,
+                5,
+                vec![
+                    hex("6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"),
+                    hex("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                    hex("bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_verify_inclusion_rfc6962_vectors() {
+        let leaves = leaves();
+        let roots = roots();
+
+        for (leaf_index, tree_size, audit_path) in inclusion_proofs() {
+            let leaf_hash = hash_leaf(&leaves[leaf_index]);
+            assert!(
+                verify_inclusion(
+                    leaf_index,
+                    tree_size,
+                    leaf_hash,
+                    &audit_path,
+                    roots[tree_size - 1]
+                ),
+                "Failed for leaf_index {} in tree_size {}",
+                leaf_index,
+                tree_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_prover_matches_rfc6962_vectors() {
+        let leaves = leaves();
+        let leaves: Vec<&[u8]> = leaves.iter().map(Vec::as_slice).collect();
+        let roots = roots();
+
+        for tree_size in 1..=leaves.len() {
+            let prover =
+                Prover::from_bytes(&leaves[..tree_size], 1).expect("Failed to create prover");
+            assert_eq!(
+                prover.get_root_hash().unwrap(),
+                roots[tree_size - 1],
+                "Failed for tree_size {}",
+                tree_size
+            );
+        }
+
+        // The prover's paths are the audit paths in root-to-leaf order
+        for (leaf_index, tree_size, audit_path) in inclusion_proofs() {
+            let prover =
+                Prover::from_bytes(&leaves[..tree_size], 1).expect("Failed to create prover");
+            let mut path = prover.get_proof(leaf_index).unwrap().authentication_path;
+            path.reverse();
+            assert_eq!(path, audit_path);
+        }
+    }
+
+    #[test]
+    fn test_consistency_proofs_match_rfc6962_vectors() {
+        let leaves = leaves();
+        let leaves: Vec<&[u8]> = leaves.iter().map(Vec::as_slice).collect();
+        let roots = roots();
+
+        // The published consistency proofs as (old_size, new_size, hashes)
+        let vectors = [
+            (1, 1, vec![]),
+            (
+                1,
+                8,
+                vec![
+                    hex("96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7"),
+                    hex("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                    hex("6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"),
+                ],
+            ),
+            (
+                6,
+                8,
+                vec![
+                    hex("0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a"),
+                    hex("ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0"),
+                    hex("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+                ],
+            ),
+            (
+                2,
+                5,
+                vec![
+                    hex("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                    hex("bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"),
+                ],
+            ),
+        ];
+
+        for (old_size, new_size, hashes) in vectors {
+            let prover =
+                Prover::from_bytes(&leaves[..new_size], 1).expect("Failed to create prover");
+            let proof = prover.consistency_proof(old_size).unwrap();
+            assert_eq!(proof.hashes, hashes);
+
+            let verifier = Verifier::new(roots[new_size - 1]);
+            assert!(verifier.verify_consistency(roots[old_size - 1], roots[new_size - 1], &proof));
+        }
+    }
+
+    #[test]
+    fn test_verify_inclusion_rejects_modified_proofs() {
+        let leaves = leaves();
+        let roots = roots();
+
+        for (leaf_index, tree_size, audit_path) in inclusion_proofs() {
+            let leaf_hash = hash_leaf(&leaves[leaf_index]);
+            let root = roots[tree_size - 1];
+
+            // Wrong root
+            let wrong_root = roots[tree_size % roots.len()];
+            assert!(!verify_inclusion(
+                leaf_index,
+                tree_size,
+                leaf_hash,
+                &audit_path,
+                wrong_root
+            ));
+
+            // Wrong leaf index or tree size
+            assert!(!verify_inclusion(
+                tree_size,
+                tree_size,
+                leaf_hash,
+                &audit_path,
+                root
+            ));
+            assert!(!verify_inclusion(
+                leaf_index,
+                tree_size * 2,
+                leaf_hash,
+                &audit_path,
+                root
+            ));
+
+            // Extended path
+            let mut extended_path = audit_path.clone();
+            extended_path.push(leaf_hash);
+            assert!(!verify_inclusion(
+                leaf_index,
+                tree_size,
+                leaf_hash,
+                &extended_path,
+                root
+            ));
+
+            // Truncated path
+            if !audit_path.is_empty() {
+                assert!(!verify_inclusion(
+                    leaf_index,
+                    tree_size,
+                    leaf_hash,
+                    &audit_path[..audit_path.len() - 1],
+                    root
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! The error type of the fallible operations of the library.
+
+use core::fmt;
+
+/// The error returned when a tree cannot be built, a proof cannot be generated or an
+/// encoded proof cannot be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    /// The data to build or extend a tree from is empty.
+    EmptyData,
+    /// The tree would hold more leaves than the maximum allowed size.
+    DataTooLarge { len: usize, max: usize },
+    /// The number of threads for the construction is zero.
+    ZeroThreads,
+    /// The thread pool for the construction could not be created.
+    ThreadPool,
+    /// A leaf or row index lies outside the tree.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// A tree size or prefix length is zero or larger than the tree.
+    SizeOutOfBounds { size: usize, len: usize },
+    /// No leaf indices were given to prove.
+    EmptyIndices,
+    /// A leaf range `[start, end)` holds no leaves.
+    EmptyRange { start: usize, end: usize },
+    /// The root node is missing.
+    MissingRoot,
+    /// The leaf lies left of the frontier of a Prover created with `from_frontier`.
+    LeafPruned { index: usize },
+    /// A node needed for a proof lies left of the frontier of a Prover created with
+    /// `from_frontier`.
+    NodePruned,
+    /// The Prover was not created in retaining mode and does not keep the leaf data.
+    DataNotRetained,
+    /// The retained data of a leaf is not valid UTF-8.
+    InvalidUtf8 { index: usize },
+    /// The leaf at `index` repeats the data of the earlier leaf at `original`.
+    DuplicateLeaf { index: usize, original: usize },
+    /// No leaf holds the requested data.
+    DataNotFound,
+    /// The frontier does not hold one hash per set bit of the tree size.
+    FrontierMismatch { len: usize, size: usize },
+    /// A compact range is empty.
+    EmptyPrefix,
+    /// A compact range does not hold one hash per set bit of its prefix length.
+    CompactRangeMismatch { len: usize, prefix: usize },
+    /// An encoded proof cannot be decoded, for the given reason.
+    MalformedProof(&'static str),
+    /// A hex encoded hash does not have 64 digits.
+    InvalidHexLength { len: usize },
+    /// A hex encoded hash has a character that is not a hex digit at byte `index`.
+    InvalidHexDigit { index: usize },
+    /// The interval between checkpoints is zero.
+    ZeroCheckpointInterval,
+    /// A checkpoint file cannot be decoded, for the given reason.
+    MalformedCheckpoint(&'static str),
+    /// A serialized tree state cannot be decoded, for the given reason.
+    MalformedState(&'static str),
+    /// The operation relies on promoting the last node of odd-sized levels, and the tree was
+    /// built with another `PairingPolicy`.
+    PairingNotSupported,
+    /// Two trees of different sizes cannot be compared leaf by leaf.
+    TreeSizeMismatch { len: usize, other: usize },
+    /// Reading or writing a checkpoint file failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::EmptyData => write!(f, "Data cannot be empty"),
+            MerkleError::DataTooLarge { len, max } => write!(
+                f,
+                "Data size {} exceeds the maximum allowed size of {}",
+                len, max
+            ),
+            MerkleError::ZeroThreads => write!(f, "Number of threads cannot be zero"),
+            MerkleError::ThreadPool => write!(f, "Failed to create the thread pool"),
+            MerkleError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} is out of bounds for length {}", index, len)
+            }
+            MerkleError::SizeOutOfBounds { size, len } => write!(
+                f,
+                "Size {} is out of bounds for a tree of {} leaves",
+                size, len
+            ),
+            MerkleError::EmptyIndices => write!(f, "Leaf indices cannot be empty"),
+            MerkleError::EmptyRange { start, end } => {
+                write!(f, "Leaf range [{}, {}) is empty", start, end)
+            }
+            MerkleError::MissingRoot => write!(f, "Root node is missing"),
+            MerkleError::LeafPruned { index } => {
+                write!(f, "Leaf {} is pruned from the tree", index)
+            }
+            MerkleError::NodePruned => write!(f, "A node needed for the proof is pruned"),
+            MerkleError::DataNotRetained => write!(f, "Leaf data is not retained"),
+            MerkleError::InvalidUtf8 { index } => {
+                write!(f, "Data of leaf {} is not valid UTF-8", index)
+            }
+            MerkleError::DuplicateLeaf { index, original } => {
+                write!(f, "Leaf {} duplicates the data of leaf {}", index, original)
+            }
+            MerkleError::DataNotFound => write!(f, "Target data not found in the tree"),
+            MerkleError::FrontierMismatch { len, size } => write!(
+                f,
+                "Frontier of {} hashes does not match the tree size {}",
+                len, size
+            ),
+            MerkleError::EmptyPrefix => write!(f, "Prefix length cannot be zero"),
+            MerkleError::CompactRangeMismatch { len, prefix } => write!(
+                f,
+                "Compact range of {} hashes does not match the prefix length {}",
+                len, prefix
+            ),
+            MerkleError::MalformedProof(reason) => write!(f, "Malformed proof: {}", reason),
+            MerkleError::InvalidHexLength { len } => {
+                write!(f, "Hex hash has {} digits instead of 64", len)
+            }
+            MerkleError::InvalidHexDigit { index } => {
+                write!(f, "Hex hash has an invalid digit at position {}", index)
+            }
+            MerkleError::ZeroCheckpointInterval => {
+                write!(f, "Checkpoint interval cannot be zero")
+            }
+            MerkleError::MalformedCheckpoint(reason) => {
+                write!(f, "Malformed checkpoint: {}", reason)
+            }
+            MerkleError::MalformedState(reason) => {
+                write!(f, "Malformed tree state: {}", reason)
+            }
+            MerkleError::PairingNotSupported => {
+                write!(f, "Operation requires the Promote pairing policy")
+            }
+            MerkleError::TreeSizeMismatch { len, other } => write!(
+                f,
+                "Tree of {} leaves cannot be compared with a tree of {} leaves",
+                len, other
+            ),
+            #[cfg(feature = "std")]
+            MerkleError::Io(kind) => write!(f, "Checkpoint I/O failed: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for MerkleError {
+    fn from(error: std::io::Error) -> Self {
+        MerkleError::Io(error.kind())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{MerkleProof, PairingPolicy, Prover, Verifier};
+
+    /// Asserts that `result` failed with `expected`, formatted as `message`.
+    fn assert_error<T>(result: Result<T, MerkleError>, expected: MerkleError, message: &str) {
+        match result {
+            Ok(_) => panic!("Expected {:?}, got Ok", expected),
+            Err(e) => {
+                assert_eq!(e, expected);
+                assert_eq!(e.to_string(), message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_construction_errors() {
+        assert_error(
+            Prover::new(&[], 1),
+            MerkleError::EmptyData,
+            "Data cannot be empty",
+        );
+        assert_error(
+            Prover::new(&["data1"], 0),
+            MerkleError::ZeroThreads,
+            "Number of threads cannot be zero",
+        );
+        assert_error(
+            Prover::from_iter((0..(1 << 20) + 1).map(|i| i.to_string()), 1),
+            MerkleError::DataTooLarge {
+                len: (1 << 20) + 1,
+                max: 1 << 20,
+            },
+            "Data size 1048577 exceeds the maximum allowed size of 1048576",
+        );
+        assert_error(
+            Prover::from_frontier(&[[0u8; 32]], 3),
+            MerkleError::FrontierMismatch { len: 1, size: 3 },
+            "Frontier of 1 hashes does not match the tree size 3",
+        );
+
+        let mut prover = Prover::new(&["data1"], 1).unwrap();
+        assert_error(
+            prover.append(&[]),
+            MerkleError::EmptyData,
+            "Data cannot be empty",
+        );
+    }
+
+    #[test]
+    fn test_proof_errors() {
+        let data = vec!["data1", "data2", "data3"];
+        let prover = Prover::new(&data, 1).unwrap();
+
+        assert_error(
+            prover.get_proof(3),
+            MerkleError::IndexOutOfBounds { index: 3, len: 3 },
+            "Index 3 is out of bounds for length 3",
+        );
+        assert_error(
+            prover.get_proofs(&[0, 5]),
+            MerkleError::IndexOutOfBounds { index: 5, len: 3 },
+            "Index 5 is out of bounds for length 3",
+        );
+        assert_error(
+            prover.get_multiproof(&[]),
+            MerkleError::EmptyIndices,
+            "Leaf indices cannot be empty",
+        );
+        assert_error(
+            prover.range_proof(2, 2),
+            MerkleError::EmptyRange { start: 2, end: 2 },
+            "Leaf range [2, 2) is empty",
+        );
+        assert_error(
+            prover.consistency_proof(4),
+            MerkleError::SizeOutOfBounds { size: 4, len: 3 },
+            "Size 4 is out of bounds for a tree of 3 leaves",
+        );
+        assert_error(
+            prover.compact_range(0),
+            MerkleError::SizeOutOfBounds { size: 0, len: 3 },
+            "Size 0 is out of bounds for a tree of 3 leaves",
+        );
+        assert_error(
+            prover.get_proof_inline(0),
+            MerkleError::DataNotRetained,
+            "Leaf data is not retained",
+        );
+        assert_error(
+            prover.generate_proof("data4"),
+            MerkleError::DataNotFound,
+            "Target data not found in the tree",
+        );
+
+        let resumed = Prover::from_frontier(&prover.frontier(), data.len()).unwrap();
+        assert_error(
+            resumed.get_proof(1),
+            MerkleError::LeafPruned { index: 1 },
+            "Leaf 1 is pruned from the tree",
+        );
+        assert_error(
+            resumed.get_multiproof(&[1]),
+            MerkleError::NodePruned,
+            "A node needed for the proof is pruned",
+        );
+        assert_error(
+            Prover::builder()
+                .data(&data)
+                .pairing(PairingPolicy::Duplicate)
+                .build()
+                .unwrap()
+                .range_proof(0, 2),
+            MerkleError::PairingNotSupported,
+            "Operation requires the Promote pairing policy",
+        );
+        assert_error(
+            Prover::new(&["data0"], 1)
+                .unwrap()
+                .diff(&Prover::new(&["data0", "data1"], 1).unwrap()),
+            MerkleError::TreeSizeMismatch { len: 1, other: 2 },
+            "Tree of 1 leaves cannot be compared with a tree of 2 leaves",
+        );
+    }
+
+    #[test]
+    fn test_decoding_and_compact_range_errors() {
+        assert_error(
+            MerkleProof::from_bytes(&[0u8; 47]),
+            MerkleError::MalformedProof("Encoded proof is too short"),
+            "Malformed proof: Encoded proof is too short",
+        );
+        assert_error(
+            Prover::deserialize(b"MKTS"),
+            MerkleError::MalformedState("Serialized state is truncated"),
+            "Malformed tree state: Serialized state is truncated",
+        );
+        assert_error(
+            Verifier::compact_range_root(0, &[]),
+            MerkleError::EmptyPrefix,
+            "Prefix length cannot be zero",
+        );
+        assert_error(
+            Verifier::compact_range_root(3, &[[0u8; 32]]),
+            MerkleError::CompactRangeMismatch { len: 1, prefix: 3 },
+            "Compact range of 1 hashes does not match the prefix length 3",
+        );
+    }
+}
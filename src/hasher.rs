@@ -2,14 +2,305 @@
 //!
 //! This module provides the basic hashing functionality utilized throughout the library,
 //!  that computes the SHA256 hash of given data sequences.
+//!
+//! The tree and the verifier are generic over the `Hasher` trait, so other digests with a
+//! 32-byte output can be plugged in. `Sha256Hasher` is the default used throughout the crate.
+//! With the `blake2` feature, `Blake2bHasher` hashes with Blake2b-256 instead.
+//!
+//! Leaves and internal nodes are hashed with distinct prefixes, as in RFC 6962, so that the
+//! data of a leaf can never be mistaken for the two child hashes of an internal node.
 
+use alloc::vec::Vec;
+#[cfg(feature = "blake2")]
+use blake2::{digest::consts::U32, Blake2b};
 use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+/// The SHA256 hash of the empty string, which is also the root hash of an empty tree
+/// (RFC 6962 §2.1).
+pub const EMPTY_HASH: [u8; 32] = [
+    0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+    0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+];
 
 /// Computes the SHA256 hash of the given data and returns the result as raw bytes.
+///
+/// Hashing no data sequences at all, or only empty ones, returns `EMPTY_HASH`.
 pub fn hash_data_sequences(datas: &[&[u8]]) -> [u8; 32] {
+    hash_data_iter(datas)
+}
+
+/// Computes the SHA256 hash of the data sequences yielded by `parts`, in order.
+///
+/// This gives the same hash as `hash_data_sequences` over the same sequences, without the
+/// caller having to collect them into a slice first.
+///
+/// # Arguments
+///
+/// * `parts` - The data sequences to hash.
+///
+/// # Returns
+///
+/// The SHA256 hash of the concatenated sequences.
+pub fn hash_data_iter<I>(parts: I) -> [u8; 32]
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut sha256 = Sha256::new();
+    for part in parts {
+        sha256.update(part.as_ref());
+    }
+    sha256.finalize().into()
+}
+
+/// Computes the SHA256 hash of the given data sequences, each preceded by its length as an
+/// 8-byte little-endian integer.
+///
+/// Unlike `hash_data_sequences`, the hash depends on where the sequences are split, so
+/// `("ab", "c")` and `("a", "bc")` hash differently.
+pub fn hash_data_sequences_prefixed(datas: &[&[u8]]) -> [u8; 32] {
     let mut sha256 = Sha256::new();
     for data in datas.iter() {
+        sha256.update((data.len() as u64).to_le_bytes());
         sha256.update(data);
     }
     sha256.finalize().into()
 }
+
+/// Prefix byte prepended to the data of a leaf before hashing.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// Prefix byte prepended to the concatenated child hashes of an internal node before hashing.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// A hash function with a 32-byte output used to build and verify Merkle trees.
+///
+/// Implementors only provide `hash`; the leaf and node hashes are derived from it with the
+/// domain separation prefixes.
+pub trait Hasher {
+    /// Computes the hash of the concatenation of the given data sequences.
+    fn hash(datas: &[&[u8]]) -> [u8; 32];
+
+    /// Computes the hash of a leaf holding `data`.
+    fn hash_leaf(data: &[u8]) -> [u8; 32] {
+        Self::hash(&[&[LEAF_PREFIX], data])
+    }
+
+    /// Computes the hash of a leaf holding `data` in a tree salted with `salt`.
+    ///
+    /// The salt follows the leaf prefix, so salted leaves stay separated from internal nodes.
+    fn hash_salted_leaf(salt: &[u8], data: &[u8]) -> [u8; 32] {
+        Self::hash(&[&[LEAF_PREFIX], salt, data])
+    }
+
+    /// Computes the hash of a leaf made of several parts, each preceded by its length as an
+    /// 8-byte little-endian integer, so that different splittings of the same bytes differ.
+    fn hash_leaf_parts(parts: &[&[u8]]) -> [u8; 32] {
+        let lengths: Vec<[u8; 8]> = parts
+            .iter()
+            .map(|part| (part.len() as u64).to_le_bytes())
+            .collect();
+        let mut datas: Vec<&[u8]> = Vec::with_capacity(2 * parts.len() + 1);
+        datas.push(&[LEAF_PREFIX]);
+        for (length, part) in lengths.iter().zip(parts) {
+            datas.push(length);
+            datas.push(part);
+        }
+        Self::hash(&datas)
+    }
+
+    /// Computes the hash of an internal node from the hashes of its two children.
+    fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        Self::hash(&[&[NODE_PREFIX], left, right])
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    /// SHA256 context reused by `Sha256Hasher` on each thread, reset after every hash.
+    static SHA256_CONTEXT: RefCell<Sha256> = RefCell::new(Sha256::new());
+}
+
+/// The default `Hasher`, computing SHA256 digests.
+///
+/// Each thread reuses one SHA256 context instead of creating a new one per hash, which
+/// saves the setup in tight loops such as folding authentication paths. Without the `std`
+/// feature there are no thread-locals, and every hash uses a fresh context. The digests are
+/// the same as those of `hash_data_sequences`.
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    #[cfg(not(feature = "std"))]
+    fn hash(datas: &[&[u8]]) -> [u8; 32] {
+        hash_data_sequences(datas)
+    }
+
+    #[cfg(feature = "std")]
+    fn hash(datas: &[&[u8]]) -> [u8; 32] {
+        SHA256_CONTEXT.with(|context| {
+            let mut sha256 = context.borrow_mut();
+            for data in datas.iter() {
+                sha256.update(data);
+            }
+            sha256.finalize_reset().into()
+        })
+    }
+}
+
+/// A `Hasher` computing Blake2b digests with a 256-bit output, for compatibility with
+/// systems built on Blake2b.
+///
+/// The output length is a Blake2b parameter, so the digests are those of Blake2b-256 and not
+/// truncated Blake2b-512 digests. Use it as `MerkleProver<Blake2bHasher>` and
+/// `MerkleVerifier<Blake2bHasher>`.
+#[cfg(feature = "blake2")]
+pub struct Blake2bHasher;
+
+#[cfg(feature = "blake2")]
+impl Hasher for Blake2bHasher {
+    fn hash(datas: &[&[u8]]) -> [u8; 32] {
+        let mut blake2b = Blake2b::<U32>::new();
+        for data in datas.iter() {
+            blake2b.update(data);
+        }
+        blake2b.finalize().into()
+    }
+}
+
+/// Computes the SHA256 hash of a leaf holding `data`.
+pub fn hash_leaf(data: &[u8]) -> [u8; 32] {
+    Sha256Hasher::hash_leaf(data)
+}
+
+/// Computes the SHA256 hash of an internal node from the hashes of its two children.
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256Hasher::hash_node(left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_hash_data_iter_matches_sequences() {
+        let parts: [&[u8]; 3] = [b"data", b"", b"sequences"];
+        assert_eq!(hash_data_iter(parts), hash_data_sequences(&parts));
+        assert_eq!(hash_data_iter(Vec::<&[u8]>::new()), EMPTY_HASH);
+
+        // Owned parts and parts split differently hash the same
+        let owned: Vec<Vec<u8>> = parts.iter().map(|part| part.to_vec()).collect();
+        assert_eq!(hash_data_iter(&owned), hash_data_sequences(&parts));
+        assert_eq!(
+            hash_data_iter(["datasequences"]),
+            hash_data_sequences(&parts)
+        );
+        assert_ne!(hash_data_iter(["data"]), hash_data_sequences(&parts));
+    }
+
+    #[test]
+    fn test_hash_data_sequences_prefixed() {
+        let first: [&[u8]; 2] = [b"ab", b"c"];
+        let second: [&[u8]; 2] = [b"a", b"bc"];
+        assert_eq!(hash_data_sequences(&first), hash_data_sequences(&second));
+        assert_ne!(
+            hash_data_sequences_prefixed(&first),
+            hash_data_sequences_prefixed(&second)
+        );
+        assert_ne!(
+            Sha256Hasher::hash_leaf_parts(&first),
+            Sha256Hasher::hash_leaf_parts(&second)
+        );
+
+        // An empty part still counts
+        assert_ne!(
+            hash_data_sequences_prefixed(&[b"abc"]),
+            hash_data_sequences_prefixed(&[b"abc", b""])
+        );
+        assert_eq!(
+            hash_data_sequences_prefixed(&[b"abc"]),
+            hash_data_sequences(&[&3u64.to_le_bytes(), b"abc"])
+        );
+        assert_eq!(
+            Sha256Hasher::hash_leaf_parts(&first),
+            hash_data_sequences(&[
+                &[LEAF_PREFIX],
+                &[2, 0, 0, 0, 0, 0, 0, 0],
+                b"ab",
+                &[1, 0, 0, 0, 0, 0, 0, 0],
+                b"c"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_hash_data_sequences_empty() {
+        assert_eq!(hash_data_sequences(&[]), EMPTY_HASH);
+        assert_eq!(hash_data_sequences(&[&[], &[]]), EMPTY_HASH);
+        assert_eq!(Sha256Hasher::hash(&[]), EMPTY_HASH);
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn test_blake2b_known_answer() {
+        let expected = [
+            0xbd, 0xdd, 0x81, 0x3c, 0x63, 0x42, 0x39, 0x72, 0x31, 0x71, 0xef, 0x3f, 0xee, 0x98,
+            0x57, 0x9b, 0x94, 0x96, 0x4e, 0x3b, 0xb1, 0xcb, 0x3e, 0x42, 0x72, 0x62, 0xc8, 0xc0,
+            0x68, 0xd5, 0x23, 0x19,
+        ];
+        assert_eq!(Blake2bHasher::hash(&[b"abc"]), expected);
+        assert_eq!(Blake2bHasher::hash(&[b"a", b"", b"bc"]), expected);
+    }
+
+    #[cfg(all(feature = "blake2", feature = "std"))]
+    #[test]
+    fn test_blake2b_tree() {
+        use crate::{MerkleProver, MerkleVerifier};
+
+        let data = ["data0", "data1", "data2"];
+        let prover = MerkleProver::<Blake2bHasher>::new(&data, 2).expect("Failed to create prover");
+
+        // The root of the tree over the three leaves, computed independently
+        let expected_root = [
+            0x8f, 0xdc, 0x7d, 0xec, 0xec, 0x24, 0xb2, 0x42, 0x70, 0xb7, 0x29, 0x01, 0x2f, 0x71,
+            0xde, 0x83, 0x35, 0xd2, 0xe1, 0xd7, 0x81, 0xe8, 0x2b, 0xfe, 0xec, 0xaa, 0xf5, 0x0c,
+            0x45, 0xb4, 0xa4, 0x83,
+        ];
+        assert_eq!(prover.root(), expected_root);
+
+        let verifier = MerkleVerifier::<Blake2bHasher>::new(prover.root());
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert!(verifier.verify_proof_for_data(leaf, &proof));
+        }
+
+        // Proofs of a Blake2b tree do not verify with SHA256, and vice versa
+        let proof = prover.get_proof(0).unwrap();
+        assert!(!crate::Verifier::new(prover.root()).verify_proof(&proof));
+        let sha256_prover = crate::Prover::new(&data, 1).unwrap();
+        assert!(!verifier.verify_proof(&sha256_prover.get_proof(0).unwrap()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reused_context_matches_fresh_context() {
+        let inputs: [&[&[u8]]; 4] = [
+            &[b"data1"],
+            &[&[LEAF_PREFIX], b"data2"],
+            &[&[NODE_PREFIX], &[1u8; 32], &[2u8; 32]],
+            &[],
+        ];
+
+        // Repeated hashing on the same thread must not carry state between digests
+        for _ in 0..2 {
+            for datas in inputs {
+                assert_eq!(Sha256Hasher::hash(datas), hash_data_sequences(datas));
+            }
+        }
+
+        let handle = std::thread::spawn(move || Sha256Hasher::hash(inputs[2]));
+        assert_eq!(handle.join().unwrap(), hash_data_sequences(inputs[2]));
+    }
+}
@@ -8,12 +8,27 @@
 //! - `prover`: Functionality for bulding Merkle tree and generating Merkle proofs from a given tree.
 //! - `verifier`: Functionality for verification of Merkle proofs.
 //! - `hasher`: Utility functions for hashing data.
+//! - `error`: The `MerkleError` type returned by fallible operations.
 //! - `merkle_proof`: Data structure for representing Merkle proofs.
+//! - `matrix`: Commitments to a two-dimensional grid of data.
+//! - `ct`: Verification of Certificate Transparency (RFC 6962) inclusion proofs.
+//! - `sparse`: Sparse Merkle trees keyed by 256-bit keys, with proofs of inclusion and non-inclusion.
+//! - `mmr`: Merkle Mountain Ranges for append-only logs.
 //!
 //! ## Features:
 //!
-//! - Efficient SHA256 hashing.
+//! - Efficient SHA256 hashing, with support for other digests through the `Hasher` trait.
+//! - Domain separation between leaf and internal node hashes (RFC 6962).
+//! - Odd-sized levels promote their last node instead of duplicating it, matching the RFC 6962 tree shape.
+//! - Other ecosystems' handling of odd-sized levels, duplicating the last node or pairing it
+//!   with zeros, through `PairingPolicy`.
 //! - Multithreading support in tree construction.
+//! - Optional serde support for `MerkleProof` through the `serde` feature.
+//! - Optional Blake2b-256 hashing through `Blake2bHasher` and the `blake2` feature.
+//! - Optional construction off the async executor with `Prover::new_async` and the `tokio`
+//!   feature.
+//! - `no_std` verification: without the default `std` feature, the verifier, the proof types
+//!   and the hashers build with only `alloc`. Tree construction requires `std`.
 //! - Comprehensive verification methods.
 
 //! # Examples
@@ -48,12 +63,41 @@
 //! ```
 //!
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod ct;
+mod error;
 mod hasher;
+#[cfg(feature = "std")]
+mod matrix;
 mod merkle_proof;
+pub mod mmr;
+#[cfg(feature = "std")]
 mod prover;
+#[cfg(feature = "std")]
+pub mod sparse;
 mod verifier;
 
-pub use hasher::hash_data_sequences;
-pub use merkle_proof::MerkleProof;
-pub use prover::Prover;
-pub use verifier::Verifier;
+pub use error::MerkleError;
+#[cfg(feature = "blake2")]
+pub use hasher::Blake2bHasher;
+pub use hasher::{
+    hash_data_iter, hash_data_sequences, hash_data_sequences_prefixed, hash_leaf, hash_node,
+    Hasher, Sha256Hasher, EMPTY_HASH, LEAF_PREFIX, NODE_PREFIX,
+};
+#[cfg(feature = "std")]
+pub use matrix::{CellProof, MatrixCommitment};
+pub use merkle_proof::{
+    check_uniform_size, CompactProof, ConsistencyProof, MerkleProof, MultiProof, PairingPolicy,
+    RangeProof, SelfContainedProof,
+};
+#[cfg(feature = "std")]
+pub use prover::{root_from_data, MerkleProver, Prover, ProverBuilder, MAX_DATA_SIZE};
+#[cfg(feature = "std")]
+pub use verifier::VerificationReceipt;
+pub use verifier::{
+    MerkleStreamingVerifier, MerkleVerifier, StreamingVerifier, Verifier, VerifyError,
+    VerifyObserver,
+};
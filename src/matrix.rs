@@ -0,0 +1,89 @@
+//! Commitments to a two-dimensional grid of data.
+//!
+//! A matrix is committed to by building a Merkle tree over each row and a top tree whose
+//! leaves are the row roots. A cell is proven by a proof in its row tree together with a
+//! proof of the row root in the top tree.
+
+use crate::hasher::Hasher;
+use crate::prover::MerkleProver;
+use crate::{MerkleError, MerkleProof};
+
+/// The proof of inclusion of a single cell in a `MatrixCommitment`.
+pub struct CellProof {
+    /// Proof of the cell in its row tree; its leaf index is the column.
+    pub cell_proof: MerkleProof,
+    /// Proof of the row root in the top tree; its leaf index is the row.
+    pub row_proof: MerkleProof,
+}
+
+/// `MatrixCommitment` holds a Merkle tree per row and a top tree over the row roots.
+pub struct MatrixCommitment<H: Hasher> {
+    rows: Vec<MerkleProver<H>>,
+    top: MerkleProver<H>,
+}
+
+impl<H: Hasher> MerkleProver<H> {
+    /// Commits to a matrix by building a tree per row and a tree over the row roots.
+    ///
+    /// The top tree treats each row root as the data of a leaf. Rows may have different
+    /// lengths.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The rows of the matrix, each a slice of string data.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the MatrixCommitment, or a `MerkleError` if any issues arise.
+    pub fn matrix(
+        rows: &[&[&str]],
+        num_threads: usize,
+    ) -> Result<MatrixCommitment<H>, MerkleError> {
+        let rows = rows
+            .iter()
+            .map(|row| Self::new(row, num_threads))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let row_roots = rows
+            .iter()
+            .map(|row| row.get_root_hash())
+            .collect::<Result<Vec<_>, _>>()?;
+        let row_roots: Vec<&[u8]> = row_roots.iter().map(|root| root.as_slice()).collect();
+        let top = Self::from_bytes(&row_roots, num_threads)?;
+
+        Ok(MatrixCommitment { rows, top })
+    }
+}
+
+impl<H: Hasher> MatrixCommitment<H> {
+    /// Retrieves the root hash of the top tree, committing to the whole matrix.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the root hash, or a `MerkleError` if the root is missing.
+    pub fn get_root_hash(&self) -> Result<[u8; 32], MerkleError> {
+        self.top.get_root_hash()
+    }
+
+    /// Generates the proof of inclusion for the cell at the given row and column.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The index of the row holding the cell.
+    /// * `column` - The index of the cell within its row.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the CellProof, or a `MerkleError` if any issues arise.
+    pub fn get_cell_proof(&self, row: usize, column: usize) -> Result<CellProof, MerkleError> {
+        let row_prover = self.rows.get(row).ok_or(MerkleError::IndexOutOfBounds {
+            index: row,
+            len: self.rows.len(),
+        })?;
+        Ok(CellProof {
+            cell_proof: row_prover.get_proof(column)?,
+            row_proof: self.top.get_proof(row)?,
+        })
+    }
+}
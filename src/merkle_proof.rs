@@ -2,9 +2,857 @@
 //!
 //! A `MerkleProof` provides evidence for the inclusion of a specific leaf in the Merkle tree. It includes
 //! the leaf's index, the hash of the leaf, and the authentication path necessary for verification.
+//!
+//! A `MultiProof` proves the inclusion of several leaves at once, sharing the internal nodes
+//! their authentication paths have in common.
+//!
+//! A `ConsistencyProof` proves that a tree is an extension of an older, smaller tree.
+
+use crate::error::MerkleError;
+use crate::hasher::{Hasher, Sha256Hasher};
+use crate::verifier::{fold_path, hashes_equal};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Length in bytes of the fixed header of the binary encoding: the leaf index, tree size
+/// and leaf hash.
+const ENCODED_HEADER_LEN: usize = 8 + 8 + 32;
+
+/// Returns the height of a tree with `leaf_count` leaves, which is also the
+/// length of the longest authentication path of a proof generated from it.
+pub(crate) fn tree_height(leaf_count: usize) -> usize {
+    // A single leaf is its own root
+    if leaf_count <= 1 {
+        return 0;
+    }
+    // ceil(log2(leaf_count)) is the bit length of leaf_count - 1
+    (usize::BITS - (leaf_count - 1).leading_zeros()) as usize
+}
+
+/// Returns the length of the authentication path of the leaf at `leaf_index` in a tree
+/// with `leaf_count` leaves.
+///
+/// A level contributes a sibling hash only if the ancestor of the leaf at that level has a
+/// sibling. The last node of an odd-sized level has none and is promoted unchanged.
+pub(crate) fn path_length(leaf_index: usize, leaf_count: usize) -> usize {
+    let (mut position, mut width, mut length) = (leaf_index, leaf_count, 0);
+    while width > 1 {
+        if position ^ 1 < width {
+            length += 1;
+        }
+        position >>= 1;
+        width = width.div_ceil(2);
+    }
+    length
+}
+
+/// Checks that a batch of proofs all claim the same tree size.
+///
+/// Proofs from mixed sources, e.g. from different versions of a tree, disagree on the size
+/// and are caught before the batch is trusted collectively.
+///
+/// # Arguments
+///
+/// * `proofs` - The proofs that should come from the same tree.
+///
+/// # Returns
+///
+/// The common `tree_size` of the proofs, or `None` if they disagree or there are none.
+pub fn check_uniform_size(proofs: &[MerkleProof]) -> Option<usize> {
+    let tree_size = proofs.first()?.tree_size;
+    proofs
+        .iter()
+        .all(|proof| proof.tree_size == tree_size)
+        .then_some(tree_size)
+}
+
+/// How the last node of an odd-sized level is combined into the next level.
+///
+/// The policy is chosen with `ProverBuilder::pairing` and must match the one given to
+/// `MerkleVerifier::with_pairing`. Every policy other than `Promote` pairs the node with a
+/// stand-in sibling, which then appears in the authentication path, so every proof has a path
+/// of the full tree height.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PairingPolicy {
+    /// The node is paired with a copy of itself, as in Bitcoin.
+    Duplicate,
+    /// The node is promoted to the next level unchanged, as in RFC 6962.
+    #[default]
+    Promote,
+    /// The node is paired with an all-zero hash.
+    HashWithZero,
+}
 
+impl PairingPolicy {
+    /// Returns the sibling `node` is paired with when it has none, or `None` if it is
+    /// promoted.
+    pub(crate) fn lone_sibling(self, node: &[u8; 32]) -> Option<[u8; 32]> {
+        match self {
+            PairingPolicy::Duplicate => Some(*node),
+            PairingPolicy::Promote => None,
+            PairingPolicy::HashWithZero => Some([0u8; 32]),
+        }
+    }
+}
+
+/// Version of the TLV encoding written by `to_tlv`.
+const TLV_VERSION: u8 = 1;
+/// Hash algorithm id of SHA256 in the TLV encoding.
+const TLV_SHA256: u8 = 1;
+
+const TLV_TAG_VERSION: u8 = 0x01;
+const TLV_TAG_HASH_ALGORITHM: u8 = 0x02;
+const TLV_TAG_LEAF_INDEX: u8 = 0x03;
+const TLV_TAG_TREE_SIZE: u8 = 0x04;
+const TLV_TAG_LEAF_HASH: u8 = 0x05;
+const TLV_TAG_PATH: u8 = 0x06;
+const TLV_TAG_LEAF_DATA: u8 = 0x87;
+/// Tags with this bit set mark optional fields, which readers skip if they do not know them.
+const TLV_OPTIONAL: u8 = 0x80;
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MerkleProof {
     pub leaf_index: usize,
+    /// The number of leaves in the tree, which determines where nodes were promoted.
+    pub tree_size: usize,
     pub leaf_hash: [u8; 32],
     pub authentication_path: Vec<[u8; 32]>,
+    /// The leaf data itself, embedded by `Prover::get_proof_inline`.
+    pub leaf_data: Option<Vec<u8>>,
+}
+
+/// Formats bytes as lowercase hex in `Debug` output.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a list of hashes as lowercase hex in `Debug` output.
+struct HexList<'a>(&'a [[u8; 32]]);
+
+impl fmt::Debug for HexList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|hash| Hex(hash)))
+            .finish()
+    }
+}
+
+impl fmt::Debug for MerkleProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MerkleProof")
+            .field("leaf_index", &self.leaf_index)
+            .field("tree_size", &self.tree_size)
+            .field("leaf_hash", &Hex(&self.leaf_hash))
+            .field("authentication_path", &HexList(&self.authentication_path))
+            .field("leaf_data", &self.leaf_data.as_deref().map(Hex))
+            .finish()
+    }
+}
+
+impl MerkleProof {
+    /// Recomputes the root hash from this proof and compares it with `root`.
+    ///
+    /// This gives the same answer as `Verifier::verify_proof` for a verifier holding `root`,
+    /// and therefore assumes the tree was hashed with SHA256.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root hash the proof is expected to lead to.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof leads to `root`, otherwise returns `false`.
+    pub fn verifies_against(&self, root: [u8; 32]) -> bool {
+        self.fits_tree_size() && hashes_equal(&fold_path::<Sha256Hasher>(self, |_| {}), &root)
+    }
+
+    /// Returns the side of each sibling in the authentication path, for formats that carry
+    /// explicit positions instead of deriving them from the leaf index.
+    ///
+    /// Levels where a node was promoted have no sibling and are skipped, as in verification.
+    ///
+    /// # Returns
+    ///
+    /// For each sibling from the leaf up to the root, which is the reverse of the order of
+    /// `authentication_path`, `true` if the sibling is on the right and `false` if it is on
+    /// the left.
+    pub fn directions(&self) -> Vec<bool> {
+        let mut directions = Vec::with_capacity(self.authentication_path.len());
+        let (mut position, mut width) = (self.leaf_index, self.tree_size);
+        while width > 1 {
+            if position ^ 1 < width {
+                directions.push(position & 1 == 0);
+            }
+            position >>= 1;
+            width = width.div_ceil(2);
+        }
+        directions
+    }
+
+    /// Drops the leaf hash and any embedded leaf data, keeping what a verifier holding the
+    /// leaf data needs.
+    ///
+    /// # Returns
+    ///
+    /// The `CompactProof` for the same leaf.
+    pub fn to_compact(&self) -> CompactProof {
+        CompactProof {
+            leaf_index: self.leaf_index,
+            tree_size: self.tree_size,
+            authentication_path: self.authentication_path.clone(),
+        }
+    }
+
+    /// Checks that the proof has the shape a genuine proof from a tree of `tree_size` leaves
+    /// built with `pairing` has, as a defense-in-depth check beyond cryptographic verification.
+    ///
+    /// A canonical proof claims `tree_size`, its path has exactly the length the leaf index
+    /// and `pairing` imply, and neither its leaf hash nor any sibling is all zeros. Where the
+    /// node has no sibling, the path entry standing in for one must be what `pairing`
+    /// prescribes: a copy of the node for `Duplicate` and zeros for `HashWithZero`, so a
+    /// duplicated node is flagged in a tree whose policy never duplicates. Sibling subtrees
+    /// that are equal, e.g. from repeated leaves, are legitimate. Like `verifies_against`, the
+    /// check assumes the tree was hashed with SHA256.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_size` - The number of leaves in the tree the proof should be from.
+    /// * `pairing` - How the tree combines the last node of an odd-sized level.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is canonical, otherwise returns `false`.
+    pub fn is_canonical(&self, tree_size: usize, pairing: PairingPolicy) -> bool {
+        if self.tree_size != tree_size || !self.fits_tree_size_with(pairing) {
+            return false;
+        }
+        if self.leaf_hash == [0u8; 32] {
+            return false;
+        }
+
+        let mut current_hash = self.leaf_hash;
+        let mut path = self.authentication_path.iter().rev();
+        let (mut position, mut width) = (self.leaf_index, tree_size);
+        while width > 1 {
+            if position ^ 1 < width {
+                let hash = path.next().expect("Path length fits the tree size");
+                if *hash == [0u8; 32] {
+                    return false;
+                }
+                current_hash = if position & 1 != 0 {
+                    Sha256Hasher::hash_node(hash, &current_hash)
+                } else {
+                    Sha256Hasher::hash_node(&current_hash, hash)
+                };
+            } else if let Some(stand_in) = pairing.lone_sibling(&current_hash) {
+                let hash = path.next().expect("Path length fits the tree size");
+                if *hash != stand_in {
+                    return false;
+                }
+                current_hash = Sha256Hasher::hash_node(&current_hash, hash);
+            }
+            position >>= 1;
+            width = width.div_ceil(2);
+        }
+        true
+    }
+
+    /// Checks that the leaf index lies inside the tree and that the authentication path has
+    /// exactly the length a leaf at that index has in a tree of `tree_size` leaves.
+    pub(crate) fn fits_tree_size(&self) -> bool {
+        self.fits_tree_size_with(PairingPolicy::Promote)
+    }
+
+    /// Checks the shape of the proof like `fits_tree_size`, for a tree built with `pairing`.
+    pub(crate) fn fits_tree_size_with(&self, pairing: PairingPolicy) -> bool {
+        let expected_length = match pairing {
+            PairingPolicy::Promote => path_length(self.leaf_index, self.tree_size),
+            _ => tree_height(self.tree_size),
+        };
+        self.leaf_index < self.tree_size && self.authentication_path.len() == expected_length
+    }
+
+    /// Heuristically flags proofs whose `leaf_hash` may really be an internal node.
+    ///
+    /// Trees built without domain separation between leaves and internal nodes allow a
+    /// proof for an internal node to pass as a leaf proof: its leaf hash is the hash of the
+    /// two children, and its authentication path is simply shorter. Every genuine proof for a
+    /// tree with `tree_size` leaves has the path length its leaf index implies in a tree of
+    /// that size, so a shorter path is flagged. This is an audit aid for proofs from systems
+    /// that do not separate leaves from nodes, not a cryptographic guarantee. Within this
+    /// crate, a leaf hash is only trustworthy once it has been recomputed from the leaf data.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_size` - The number of leaves in the tree the proof claims to belong to.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof could be a forged internal node proof, otherwise `false`.
+    pub fn is_potentially_forged(&self, tree_size: usize) -> bool {
+        self.authentication_path.len() < path_length(self.leaf_index, tree_size)
+    }
+
+    /// Returns the exact length in bytes of the encoding produced by `to_bytes`.
+    pub fn serialized_len(&self) -> usize {
+        ENCODED_HEADER_LEN + 32 * self.authentication_path.len()
+    }
+
+    /// Encodes the proof in a compact binary format.
+    ///
+    /// The layout is the leaf index and the tree size as 8 bytes little-endian each, followed
+    /// by the 32-byte leaf hash and the authentication path hashes concatenated in order.
+    /// Embedded leaf data is not part of the encoding.
+    ///
+    /// # Returns
+    ///
+    /// The encoded proof, `48 + 32 * k` bytes long for a path of `k` hashes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_len());
+        bytes.extend_from_slice(&(self.leaf_index as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.tree_size as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.leaf_hash);
+        for hash in self.authentication_path.iter() {
+            bytes.extend_from_slice(hash);
+        }
+        bytes
+    }
+
+    /// Decodes a proof from the binary format produced by `to_bytes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded proof.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the decoded MerkleProof without leaf data, or a `MerkleError` if
+    /// the input is not `48 + 32 * k` bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MerkleProof, MerkleError> {
+        if bytes.len() < ENCODED_HEADER_LEN {
+            return Err(MerkleError::MalformedProof("Encoded proof is too short"));
+        }
+        if !(bytes.len() - ENCODED_HEADER_LEN).is_multiple_of(32) {
+            return Err(MerkleError::MalformedProof(
+                "Encoded authentication path is not a whole number of hashes",
+            ));
+        }
+
+        let (index_bytes, rest) = bytes.split_at(8);
+        let (size_bytes, rest) = rest.split_at(8);
+        let (leaf_hash, path_bytes) = rest.split_at(32);
+
+        let leaf_index = u64::from_le_bytes(index_bytes.try_into().unwrap());
+        let leaf_index = usize::try_from(leaf_index)
+            .map_err(|_| MerkleError::MalformedProof("Leaf index is too large"))?;
+        let tree_size = u64::from_le_bytes(size_bytes.try_into().unwrap());
+        let tree_size = usize::try_from(tree_size)
+            .map_err(|_| MerkleError::MalformedProof("Tree size is too large"))?;
+
+        Ok(MerkleProof {
+            leaf_index,
+            tree_size,
+            leaf_hash: leaf_hash.try_into().unwrap(),
+            authentication_path: path_bytes
+                .chunks_exact(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+            leaf_data: None,
+        })
+    }
+
+    /// Encodes the proof in a self-describing Type-Length-Value format.
+    ///
+    /// Each field is a 1-byte type tag, a 4-byte little-endian length and the value. Integers
+    /// are 8 bytes little-endian. The fields are written in the order of their tags:
+    ///
+    /// | Tag    | Field               | Value                                               |
+    /// |--------|---------------------|-----------------------------------------------------|
+    /// | `0x01` | Version             | 1 byte, currently `1`                               |
+    /// | `0x02` | Hash algorithm      | 1 byte, `1` for SHA256                              |
+    /// | `0x03` | Leaf index          | 8 bytes                                             |
+    /// | `0x04` | Tree size           | 8 bytes                                             |
+    /// | `0x05` | Leaf hash           | 32 bytes                                            |
+    /// | `0x06` | Authentication path | `32 * k` bytes, ordered from the root to the leaf   |
+    /// | `0x87` | Leaf data           | Any length, only present if the proof has leaf data |
+    ///
+    /// Tags with the high bit `0x80` set are optional: readers skip them if they do not know
+    /// them, and reject unknown tags without it. Like `verifies_against`, the encoding assumes
+    /// the tree was hashed with SHA256.
+    ///
+    /// # Returns
+    ///
+    /// The TLV encoded proof.
+    pub fn to_tlv(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut push_field = |tag: u8, value: &[u8]| {
+            bytes.push(tag);
+            bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(value);
+        };
+
+        push_field(TLV_TAG_VERSION, &[TLV_VERSION]);
+        push_field(TLV_TAG_HASH_ALGORITHM, &[TLV_SHA256]);
+        push_field(TLV_TAG_LEAF_INDEX, &(self.leaf_index as u64).to_le_bytes());
+        push_field(TLV_TAG_TREE_SIZE, &(self.tree_size as u64).to_le_bytes());
+        push_field(TLV_TAG_LEAF_HASH, &self.leaf_hash);
+        push_field(TLV_TAG_PATH, &self.authentication_path.concat());
+        if let Some(leaf_data) = &self.leaf_data {
+            push_field(TLV_TAG_LEAF_DATA, leaf_data);
+        }
+        bytes
+    }
+
+    /// Decodes a proof from the TLV format produced by `to_tlv`.
+    ///
+    /// The fields may appear in any order, and unknown optional fields are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The TLV encoded proof.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the decoded MerkleProof, or a `MerkleError` if a field is
+    /// truncated, malformed, repeated or missing, or the version or hash algorithm is not
+    /// supported.
+    pub fn from_tlv(bytes: &[u8]) -> Result<MerkleProof, MerkleError> {
+        let mut version = None;
+        let mut hash_algorithm = None;
+        let mut leaf_index = None;
+        let mut tree_size = None;
+        let mut leaf_hash = None;
+        let mut authentication_path = None;
+        let mut leaf_data = None;
+
+        let mut rest = bytes;
+        while let Some((&tag, after_tag)) = rest.split_first() {
+            if after_tag.len() < 4 {
+                return Err(MerkleError::MalformedProof("TLV field length is truncated"));
+            }
+            let (length, after_length) = after_tag.split_at(4);
+            let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+            if after_length.len() < length {
+                return Err(MerkleError::MalformedProof("TLV field value is truncated"));
+            }
+            let (value, after_value) = after_length.split_at(length);
+            rest = after_value;
+
+            let slot = match tag {
+                TLV_TAG_VERSION => &mut version,
+                TLV_TAG_HASH_ALGORITHM => &mut hash_algorithm,
+                TLV_TAG_LEAF_INDEX => &mut leaf_index,
+                TLV_TAG_TREE_SIZE => &mut tree_size,
+                TLV_TAG_LEAF_HASH => &mut leaf_hash,
+                TLV_TAG_PATH => &mut authentication_path,
+                TLV_TAG_LEAF_DATA => &mut leaf_data,
+                _ if tag & TLV_OPTIONAL != 0 => continue,
+                _ => return Err(MerkleError::MalformedProof("Unknown required TLV field")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(MerkleError::MalformedProof("Repeated TLV field"));
+            }
+        }
+
+        if version.ok_or(MerkleError::MalformedProof("Missing TLV version field"))? != [TLV_VERSION]
+        {
+            return Err(MerkleError::MalformedProof("Unsupported TLV version"));
+        }
+        if hash_algorithm.ok_or(MerkleError::MalformedProof(
+            "Missing TLV hash algorithm field",
+        ))? != [TLV_SHA256]
+        {
+            return Err(MerkleError::MalformedProof("Unsupported hash algorithm"));
+        }
+
+        let leaf_index: [u8; 8] = leaf_index
+            .ok_or(MerkleError::MalformedProof("Missing TLV leaf index field"))?
+            .try_into()
+            .map_err(|_| MerkleError::MalformedProof("Malformed TLV leaf index field"))?;
+        let leaf_index = usize::try_from(u64::from_le_bytes(leaf_index))
+            .map_err(|_| MerkleError::MalformedProof("Leaf index is too large"))?;
+        let tree_size: [u8; 8] = tree_size
+            .ok_or(MerkleError::MalformedProof("Missing TLV tree size field"))?
+            .try_into()
+            .map_err(|_| MerkleError::MalformedProof("Malformed TLV tree size field"))?;
+        let tree_size = usize::try_from(u64::from_le_bytes(tree_size))
+            .map_err(|_| MerkleError::MalformedProof("Tree size is too large"))?;
+        let leaf_hash: [u8; 32] = leaf_hash
+            .ok_or(MerkleError::MalformedProof("Missing TLV leaf hash field"))?
+            .try_into()
+            .map_err(|_| MerkleError::MalformedProof("Malformed TLV leaf hash field"))?;
+        let path_bytes = authentication_path.ok_or(MerkleError::MalformedProof(
+            "Missing TLV authentication path field",
+        ))?;
+        if !path_bytes.len().is_multiple_of(32) {
+            return Err(MerkleError::MalformedProof(
+                "Encoded authentication path is not a whole number of hashes",
+            ));
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            tree_size,
+            leaf_hash,
+            authentication_path: path_bytes
+                .chunks_exact(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+            leaf_data: leaf_data.map(<[u8]>::to_vec),
+        })
+    }
+}
+
+/// A `MerkleProof` bundled with the root hash of its tree, so it can be handed to a verifier
+/// as a single value.
+///
+/// Verifying the bundle only shows that the proof is consistent with the embedded root. It
+/// says nothing about whether that root is the genuine one: anyone can build a tree and bundle
+/// a proof with its root. The root must still be checked against one from a trusted source.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfContainedProof {
+    /// The proof of inclusion of the leaf.
+    pub proof: MerkleProof,
+    /// The root hash of the tree the proof is for.
+    pub root: [u8; 32],
+}
+
+/// A `MerkleProof` without its leaf hash, for verifiers that already hold the leaf data.
+///
+/// The leaf hash is recomputed from the data by `Verifier::verify_compact`, which saves 32
+/// bytes per proof. The tree size is kept, since it determines where nodes were promoted and
+/// therefore how the path is folded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactProof {
+    /// The index of the leaf.
+    pub leaf_index: usize,
+    /// The number of leaves in the tree.
+    pub tree_size: usize,
+    /// The sibling hashes from the root down to the leaf.
+    pub authentication_path: Vec<[u8; 32]>,
+}
+
+/// Proof of inclusion for several leaves of the same tree.
+///
+/// Sibling hashes are only included where they cannot be computed from the proven leaves.
+/// They are ordered level by level from the leaves up, and from left to right within a
+/// level, which is the order in which the verifier consumes them.
+pub struct MultiProof {
+    /// The number of leaves in the tree.
+    pub tree_size: usize,
+    /// The indices of the proven leaves, sorted and without duplicates.
+    pub leaf_indices: Vec<usize>,
+    /// The hashes of the proven leaves, in the order of `leaf_indices`.
+    pub leaf_hashes: Vec<[u8; 32]>,
+    /// The sibling hashes needed to recompute the root, each included once.
+    pub sibling_hashes: Vec<[u8; 32]>,
+}
+
+/// Proof of inclusion for a contiguous range of leaves of the same tree.
+///
+/// Only the sibling hashes on the two boundaries of the range are included; every other node
+/// above the range is computed from the leaves. The sibling hashes are ordered level by level
+/// from the leaves up, with the left boundary sibling before the right one within a level.
+pub struct RangeProof {
+    /// The number of leaves in the tree.
+    pub tree_size: usize,
+    /// The index of the first proven leaf.
+    pub start: usize,
+    /// The hashes of the proven leaves `[start, start + leaf_hashes.len())`.
+    pub leaf_hashes: Vec<[u8; 32]>,
+    /// The boundary sibling hashes needed to recompute the root.
+    pub sibling_hashes: Vec<[u8; 32]>,
+}
+
+/// Proof that a tree of `new_size` leaves extends a tree of its first `old_size` leaves,
+/// as specified in RFC 6962.
+pub struct ConsistencyProof {
+    /// The number of leaves in the old tree.
+    pub old_size: usize,
+    /// The number of leaves in the new tree.
+    pub new_size: usize,
+    /// The subtree hashes needed to compute both roots, in the order of RFC 6962.
+    pub hashes: Vec<[u8; 32]>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{hash_leaf, hash_node};
+    use alloc::{format, vec};
+
+    fn sample_proof() -> MerkleProof {
+        MerkleProof {
+            leaf_index: 0x0102_0304,
+            tree_size: 0x0506_0708,
+            leaf_hash: [7u8; 32],
+            authentication_path: vec![[1u8; 32], [2u8; 32], [3u8; 32]],
+            leaf_data: None,
+        }
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let proof = sample_proof();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 48 + 32 * 3);
+        assert_eq!(&bytes[..8], &0x0102_0304u64.to_le_bytes());
+        assert_eq!(&bytes[8..16], &0x0506_0708u64.to_le_bytes());
+
+        let decoded = MerkleProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.leaf_index, proof.leaf_index);
+        assert_eq!(decoded.tree_size, proof.tree_size);
+        assert_eq!(decoded.leaf_hash, proof.leaf_hash);
+        assert_eq!(decoded.authentication_path, proof.authentication_path);
+        assert!(decoded.leaf_data.is_none());
+    }
+
+    #[test]
+    fn test_clone_and_equality() {
+        let proof = sample_proof();
+        let cloned = proof.clone();
+        assert_eq!(cloned, proof);
+        assert_eq!(MerkleProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+
+        // Proofs differing in any field are unequal
+        let mut other = proof.clone();
+        other.leaf_index += 1;
+        assert_ne!(other, proof);
+        let mut other = proof.clone();
+        other.leaf_hash = [8u8; 32];
+        assert_ne!(other, proof);
+        let mut other = proof.clone();
+        other.authentication_path[1] = [0u8; 32];
+        assert_ne!(other, proof);
+        let mut other = proof.clone();
+        other.leaf_data = Some(vec![1]);
+        assert_ne!(other, proof);
+    }
+
+    #[test]
+    fn test_serialized_len_matches_to_bytes() {
+        let mut proof = sample_proof();
+        for _ in 0..4 {
+            assert_eq!(proof.serialized_len(), proof.to_bytes().len());
+            proof.authentication_path.pop();
+        }
+    }
+
+    #[test]
+    fn test_debug_formats_hashes_as_hex() {
+        let proof = MerkleProof {
+            leaf_index: 1,
+            tree_size: 3,
+            leaf_hash: hash_leaf(b"data2"),
+            authentication_path: vec![hash_leaf(b"data3"), hash_leaf(b"data1")],
+            leaf_data: Some(b"data2".to_vec()),
+        };
+        let debug = format!("{:?}", proof);
+
+        assert_eq!(
+            debug,
+            "MerkleProof { leaf_index: 1, tree_size: 3, \
+             leaf_hash: a6a589d33d24456802ad8a72509e4baac378aa7b6acbc8876bf1b4e85cb37db8, \
+             authentication_path: [\
+             36ad9c5ee3a26e916a48661b2b9de0d476419299c1899ec3b2ff71b535b929b2, \
+             fd740c25469b6cfab83eb5dd939065dee203d68775efbe1d64d8e9a8bc4b0669], \
+             leaf_data: Some(6461746132) }"
+        );
+    }
+
+    #[test]
+    fn test_bytes_round_trip_empty_path() {
+        let mut proof = sample_proof();
+        proof.authentication_path.clear();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 48);
+
+        let decoded = MerkleProof::from_bytes(&bytes).unwrap();
+        assert!(decoded.authentication_path.is_empty());
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_truncated_input() {
+        let bytes = sample_proof().to_bytes();
+        assert!(MerkleProof::from_bytes(&[]).is_err());
+        assert!(MerkleProof::from_bytes(&bytes[..47]).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_misaligned_input() {
+        let bytes = sample_proof().to_bytes();
+        assert!(MerkleProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(MerkleProof::from_bytes(&bytes[..49]).is_err());
+
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(MerkleProof::from_bytes(&extended).is_err());
+    }
+
+    #[test]
+    fn test_tlv_round_trip() {
+        let mut proof = sample_proof();
+        let decoded = MerkleProof::from_tlv(&proof.to_tlv()).unwrap();
+        assert_eq!(decoded.leaf_index, proof.leaf_index);
+        assert_eq!(decoded.tree_size, proof.tree_size);
+        assert_eq!(decoded.leaf_hash, proof.leaf_hash);
+        assert_eq!(decoded.authentication_path, proof.authentication_path);
+        assert!(decoded.leaf_data.is_none());
+
+        proof.leaf_data = Some(b"data".to_vec());
+        let decoded = MerkleProof::from_tlv(&proof.to_tlv()).unwrap();
+        assert_eq!(decoded.leaf_data.as_deref(), Some("data".as_bytes()));
+    }
+
+    #[test]
+    fn test_tlv_skips_unknown_optional_fields() {
+        let proof = sample_proof();
+        let mut bytes = vec![0xf0, 3, 0, 0, 0, 1, 2, 3];
+        bytes.extend(proof.to_tlv());
+        bytes.extend([0xf1, 0, 0, 0, 0]);
+
+        let decoded = MerkleProof::from_tlv(&bytes).unwrap();
+        assert_eq!(decoded.leaf_index, proof.leaf_index);
+        assert_eq!(decoded.authentication_path, proof.authentication_path);
+
+        // Unknown fields without the optional bit cannot be ignored
+        let mut bytes = proof.to_tlv();
+        bytes.extend([0x70, 1, 0, 0, 0, 0]);
+        assert_eq!(
+            MerkleProof::from_tlv(&bytes).err(),
+            Some(MerkleError::MalformedProof("Unknown required TLV field"))
+        );
+    }
+
+    #[test]
+    fn test_from_tlv_rejects_malformed_input() {
+        let bytes = sample_proof().to_tlv();
+        assert_eq!(
+            MerkleProof::from_tlv(&bytes[..bytes.len() - 1]).err(),
+            Some(MerkleError::MalformedProof("TLV field value is truncated"))
+        );
+        assert_eq!(
+            MerkleProof::from_tlv(&bytes[6..]).err(),
+            Some(MerkleError::MalformedProof("Missing TLV version field"))
+        );
+
+        let mut unsupported = bytes.clone();
+        unsupported[5] = 2;
+        assert_eq!(
+            MerkleProof::from_tlv(&unsupported).err(),
+            Some(MerkleError::MalformedProof("Unsupported TLV version"))
+        );
+
+        let mut repeated = bytes.clone();
+        repeated.extend(&bytes[..6]);
+        assert_eq!(
+            MerkleProof::from_tlv(&repeated).err(),
+            Some(MerkleError::MalformedProof("Repeated TLV field"))
+        );
+    }
+
+    #[test]
+    fn test_check_uniform_size() {
+        let proof = |leaf_index, tree_size| MerkleProof {
+            leaf_index,
+            tree_size,
+            leaf_hash: hash_leaf(b"data"),
+            authentication_path: vec![],
+            leaf_data: None,
+        };
+
+        assert_eq!(
+            check_uniform_size(&[proof(0, 5), proof(3, 5), proof(4, 5)]),
+            Some(5)
+        );
+        assert_eq!(check_uniform_size(&[proof(0, 5), proof(3, 6)]), None);
+        assert_eq!(check_uniform_size(&[proof(2, 3)]), Some(3));
+        assert_eq!(check_uniform_size(&[]), None);
+    }
+
+    #[test]
+    fn test_is_canonical() {
+        let leaves = [
+            hash_leaf(b"data1"),
+            hash_leaf(b"data2"),
+            hash_leaf(b"data3"),
+        ];
+        let proof = MerkleProof {
+            leaf_index: 1,
+            tree_size: 3,
+            leaf_hash: leaves[1],
+            authentication_path: vec![leaves[2], leaves[0]],
+            leaf_data: None,
+        };
+        assert!(proof.is_canonical(3, PairingPolicy::Promote));
+        assert!(!proof.is_canonical(4, PairingPolicy::Promote));
+
+        let zeroed = MerkleProof {
+            authentication_path: vec![[0u8; 32], leaves[0]],
+            ..proof
+        };
+        assert!(!zeroed.is_canonical(3, PairingPolicy::Promote));
+    }
+
+    #[test]
+    fn test_repeated_leaves_are_canonical() {
+        let leaf = hash_leaf(b"a");
+        let proof = MerkleProof {
+            leaf_index: 0,
+            tree_size: 2,
+            leaf_hash: leaf,
+            authentication_path: vec![leaf],
+            leaf_data: None,
+        };
+        assert!(proof.is_canonical(2, PairingPolicy::Promote));
+        assert!(proof.is_canonical(2, PairingPolicy::Duplicate));
+        assert!(proof.is_canonical(2, PairingPolicy::HashWithZero));
+    }
+
+    #[test]
+    fn test_duplicated_node_is_canonical_only_when_duplicating() {
+        let leaves = [
+            hash_leaf(b"data1"),
+            hash_leaf(b"data2"),
+            hash_leaf(b"data3"),
+        ];
+
+        // The third leaf padded with a copy of itself, as a duplicating tree of 3 leaves has it
+        let duplicated = MerkleProof {
+            leaf_index: 2,
+            tree_size: 3,
+            leaf_hash: leaves[2],
+            authentication_path: vec![hash_node(&leaves[0], &leaves[1]), leaves[2]],
+            leaf_data: None,
+        };
+        assert!(duplicated.is_canonical(3, PairingPolicy::Duplicate));
+        assert!(!duplicated.is_canonical(3, PairingPolicy::Promote));
+        assert!(!duplicated.is_canonical(3, PairingPolicy::HashWithZero));
+
+        // Padded with zeros instead, as a tree hashing with zero has it
+        let zero_padded = MerkleProof {
+            authentication_path: vec![hash_node(&leaves[0], &leaves[1]), [0u8; 32]],
+            ..duplicated.clone()
+        };
+        assert!(zero_padded.is_canonical(3, PairingPolicy::HashWithZero));
+        assert!(!zero_padded.is_canonical(3, PairingPolicy::Duplicate));
+
+        // Under promotion the third leaf has no sibling at all
+        let promoted = MerkleProof {
+            authentication_path: vec![hash_node(&leaves[0], &leaves[1])],
+            ..duplicated
+        };
+        assert!(promoted.is_canonical(3, PairingPolicy::Promote));
+        assert!(!promoted.is_canonical(3, PairingPolicy::Duplicate));
+    }
 }
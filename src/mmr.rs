@@ -0,0 +1,296 @@
+//! Merkle Mountain Ranges for append-only logs.
+//!
+//! A Merkle Mountain Range (MMR) is a list of perfect binary trees, the peaks, whose sizes are
+//! the set bits of the number of leaves, from the largest on the left to the smallest on the
+//! right. Appending a leaf adds a peak of height 0 and merges equal-height peaks, so an append
+//! costs O(log n) hashes and never rebuilds the existing trees.
+//!
+//! The peaks are bagged into a single root hash from right to left: the two rightmost peaks
+//! are hashed as an internal node, and the result is hashed with the next peak to its left,
+//! and so on. This is how `Verifier::compact_range_root` combines subtree roots, so the root
+//! of an MMR equals the root hash of a `Prover` built over the same leaves.
+
+use alloc::vec::Vec;
+
+use crate::error::MerkleError;
+use crate::hasher::{hash_leaf, hash_node, EMPTY_HASH};
+
+/// A proof that a leaf is included in a `Mmr`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    /// The index of the leaf, in order of appending.
+    pub leaf_index: usize,
+    /// The number of leaves in the MMR the proof was generated for.
+    pub leaf_count: usize,
+    /// The hash of the leaf.
+    pub leaf_hash: [u8; 32],
+    /// The sibling hashes from the leaf up to the root of its peak.
+    pub siblings: Vec<[u8; 32]>,
+    /// The hashes of all peaks, from left to right.
+    pub peaks: Vec<[u8; 32]>,
+}
+
+/// A Merkle Mountain Range over leaf data appended one at a time.
+///
+/// The complete subtree roots are stored level by level, so level `h` holds one node for
+/// every `2^h` appended leaves.
+#[derive(Default)]
+pub struct Mmr {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl Mmr {
+    /// Creates an empty MMR.
+    pub fn new() -> Self {
+        Mmr { levels: Vec::new() }
+    }
+
+    /// Returns the number of leaves appended to the MMR.
+    pub fn leaf_count(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Appends a leaf, merging the peaks of equal height it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the new leaf.
+    pub fn append(&mut self, data: &str) {
+        let mut node = hash_leaf(data.as_bytes());
+        let mut level = 0;
+        loop {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            self.levels[level].push(node);
+            // An even number of nodes means the new node completed a pair
+            let nodes = &self.levels[level];
+            if nodes.len() % 2 == 1 {
+                break;
+            }
+            node = hash_node(&nodes[nodes.len() - 2], &nodes[nodes.len() - 1]);
+            level += 1;
+        }
+    }
+
+    /// Returns the hashes of the peaks, from left to right.
+    pub fn peaks(&self) -> Vec<[u8; 32]> {
+        let leaf_count = self.leaf_count();
+        (0..self.levels.len())
+            .rev()
+            .filter(|&level| leaf_count & (1 << level) != 0)
+            .map(|level| self.levels[level][(leaf_count >> level) - 1])
+            .collect()
+    }
+
+    /// Returns the root hash, bagging the peaks from right to left.
+    ///
+    /// The root of an empty MMR is `EMPTY_HASH`.
+    pub fn root(&self) -> [u8; 32] {
+        bag_peaks(&self.peaks()).unwrap_or(EMPTY_HASH)
+    }
+
+    /// Generates a proof of inclusion for a leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf, in order of appending.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MmrProof, or a `MerkleError` if the leaf index is
+    /// out of bounds.
+    pub fn prove(&self, leaf_index: usize) -> Result<MmrProof, MerkleError> {
+        let leaf_count = self.leaf_count();
+        if leaf_index >= leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: leaf_count,
+            });
+        }
+
+        let (_, height) = peak_of(leaf_index, leaf_count);
+        let siblings = (0..height)
+            .map(|level| self.levels[level][(leaf_index >> level) ^ 1])
+            .collect();
+
+        Ok(MmrProof {
+            leaf_index,
+            leaf_count,
+            leaf_hash: self.levels[0][leaf_index],
+            siblings,
+            peaks: self.peaks(),
+        })
+    }
+}
+
+/// Returns the position among the peaks, and the height, of the peak containing a leaf.
+fn peak_of(leaf_index: usize, leaf_count: usize) -> (usize, usize) {
+    let mut first_leaf = 0;
+    let mut position = 0;
+    for height in (0..usize::BITS as usize).rev() {
+        if leaf_count & (1 << height) == 0 {
+            continue;
+        }
+        first_leaf += 1 << height;
+        if leaf_index < first_leaf {
+            return (position, height);
+        }
+        position += 1;
+    }
+    unreachable!("The leaf index is below the leaf count")
+}
+
+/// Bags the peaks into a single hash from right to left, or returns `None` without peaks.
+fn bag_peaks(peaks: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut peaks = peaks.iter().rev();
+    let last_peak = *peaks.next()?;
+    Some(peaks.fold(last_peak, |bag, peak| hash_node(peak, &bag)))
+}
+
+/// Verifies a proof of inclusion against the root of a `Mmr`.
+///
+/// The siblings are folded into the peak containing the leaf, which must match the peak in
+/// the proof, and the peaks are bagged into the root.
+///
+/// # Arguments
+///
+/// * `root` - The expected root hash of the MMR.
+/// * `proof` - The proof to verify.
+///
+/// # Returns
+///
+/// Returns `true` if the leaf is included in the MMR with root `root`, otherwise returns
+/// `false`.
+pub fn verify_mmr_proof(root: [u8; 32], proof: &MmrProof) -> bool {
+    if proof.leaf_index >= proof.leaf_count
+        || proof.peaks.len() != proof.leaf_count.count_ones() as usize
+    {
+        return false;
+    }
+    let (position, height) = peak_of(proof.leaf_index, proof.leaf_count);
+    if proof.siblings.len() != height {
+        return false;
+    }
+
+    let mut node = proof.leaf_hash;
+    for (level, sibling) in proof.siblings.iter().enumerate() {
+        node = if (proof.leaf_index >> level) & 1 == 1 {
+            hash_node(sibling, &node)
+        } else {
+            hash_node(&node, sibling)
+        };
+    }
+    node == proof.peaks[position] && bag_peaks(&proof.peaks) == Some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn test_append_changes_root() {
+        let mut mmr = Mmr::new();
+        assert_eq!(mmr.leaf_count(), 0);
+        assert_eq!(mmr.root(), EMPTY_HASH);
+
+        let mut roots = Vec::new();
+        for i in 0..20 {
+            mmr.append(&format!("data{}", i));
+            assert_eq!(mmr.leaf_count(), i + 1);
+            assert_eq!(mmr.peaks().len(), (i + 1).count_ones() as usize);
+            assert!(!roots.contains(&mmr.root()));
+            roots.push(mmr.root());
+        }
+
+        let mut single = Mmr::new();
+        single.append("data0");
+        assert_eq!(single.root(), hash_leaf(b"data0"));
+        assert_eq!(roots[0], single.root());
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let mut mmr = Mmr::new();
+        for i in 0..13 {
+            mmr.append(&format!("data{}", i));
+
+            // Every leaf verifies against the current root
+            for leaf_index in 0..mmr.leaf_count() {
+                let proof = mmr.prove(leaf_index).unwrap();
+                assert_eq!(
+                    proof.leaf_hash,
+                    hash_leaf(format!("data{}", leaf_index).as_bytes())
+                );
+                assert!(
+                    verify_mmr_proof(mmr.root(), &proof),
+                    "Failed for leaf {} of {}",
+                    leaf_index,
+                    mmr.leaf_count()
+                );
+            }
+        }
+        assert!(mmr.prove(13).is_err());
+
+        // Peaks of 8, 4 and 1 leaves: leaf 9 has two siblings in the second peak
+        let proof = mmr.prove(9).unwrap();
+        assert_eq!(proof.siblings.len(), 2);
+        assert_eq!(proof.peaks.len(), 3);
+
+        // A proof does not verify against an older root
+        let mut older = Mmr::new();
+        for i in 0..12 {
+            older.append(&format!("data{}", i));
+        }
+        assert!(!verify_mmr_proof(older.root(), &proof));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_proofs() {
+        let mut mmr = Mmr::new();
+        for i in 0..11 {
+            mmr.append(&format!("data{}", i));
+        }
+        let root = mmr.root();
+        let proof = mmr.prove(5).unwrap();
+        assert!(verify_mmr_proof(root, &proof));
+
+        let mut tampered = proof.clone();
+        tampered.leaf_hash = hash_leaf(b"other");
+        assert!(!verify_mmr_proof(root, &tampered));
+
+        let mut tampered = proof.clone();
+        tampered.siblings[0] = [0u8; 32];
+        assert!(!verify_mmr_proof(root, &tampered));
+
+        let mut tampered = proof.clone();
+        tampered.leaf_index = 4;
+        assert!(!verify_mmr_proof(root, &tampered));
+
+        let mut tampered = proof.clone();
+        tampered.peaks[2] = [0u8; 32];
+        assert!(!verify_mmr_proof(root, &tampered));
+
+        let mut tampered = proof.clone();
+        tampered.siblings.pop();
+        assert!(!verify_mmr_proof(root, &tampered));
+
+        let mut tampered = proof;
+        tampered.leaf_count = 12;
+        assert!(!verify_mmr_proof(root, &tampered));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_root_matches_prover() {
+        let data: Vec<String> = (0..37).map(|i| format!("data{}", i)).collect();
+        let mut mmr = Mmr::new();
+        for size in 1..=data.len() {
+            mmr.append(&data[size - 1]);
+            let leaves: Vec<&str> = data[..size].iter().map(String::as_str).collect();
+            let prover = crate::Prover::new(&leaves, 1).unwrap();
+            assert_eq!(mmr.root(), prover.root(), "Failed for {} leaves", size);
+        }
+    }
+}
@@ -4,36 +4,136 @@
 //! for given leaf indices. This implementation supports multithreading for efficient tree
 //! construction.
 
-use crate::hash_data_sequences;
-use crate::MerkleProof;
+use crate::hasher::{Hasher, Sha256Hasher};
+use crate::merkle_proof::{path_length, tree_height};
+use crate::{
+    ConsistencyProof, MerkleError, MerkleProof, MerkleVerifier, MultiProof, PairingPolicy,
+    RangeProof, SelfContainedProof,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::sync::OnceLock;
 
 extern crate rayon;
 use rayon::prelude::*;
 
-const MAX_DATA_SIZE: usize = 1 << 20;
+/// The default maximum number of leaves in a tree, inclusive: a tree may hold exactly
+/// `MAX_DATA_SIZE` leaves, and building or growing it beyond that fails with
+/// `MerkleError::DataTooLarge`. `ProverBuilder::max_leaves` sets another limit.
+pub const MAX_DATA_SIZE: usize = 1 << 20;
 
-/// Represents a node in the Merkle tree.
+/// The number of requested proofs from which `get_proofs` generates them in parallel.
+const PARALLEL_PROOFS_THRESHOLD: usize = 256;
+
+/// The magic bytes starting a tree state written by `serialize`.
+const STATE_MAGIC: &[u8; 4] = b"MKTS";
+/// The version of the tree state format.
+const STATE_VERSION: u8 = 3;
+
+/// Computes the root hash of the tree `Prover::new` would build over `data`, without
+/// keeping the tree.
+///
+/// The tree is reduced one level at a time, and each level is dropped once its parents are
+/// computed, so only the leaf hashes and the level above them are held at any time.
+///
+/// # Arguments
+///
+/// * `data` - A slice of string data to compute the root hash of.
+/// * `num_threads` - The number of threads to be used in the parallel computation.
+///
+/// # Returns
+///
+/// A Result containing the root hash, or a `MerkleError` if any issues arise.
+pub fn root_from_data(data: &[&str], num_threads: usize) -> Result<[u8; 32], MerkleError> {
+    Prover::validate(data.len(), num_threads)?;
+    Prover::in_thread_pool(num_threads, || {
+        let mut level: Vec<[u8; 32]> = data
+            .par_iter()
+            .map(|d| Sha256Hasher::hash_leaf(d.as_bytes()))
+            .collect();
+        while level.len() > 1 {
+            level = level
+                .par_chunks(2)
+                .map(|children| Prover::parent_hash(children, PairingPolicy::Promote))
+                .collect();
+        }
+        level[0]
+    })
+}
+
+/// Returns the number of nodes at `level` of a tree with `leaf_count` leaves.
+fn level_width(leaf_count: usize, level: usize) -> usize {
+    ((leaf_count - 1) >> level) + 1
+}
+
+/// Returns the position of the first node kept at `level` when the first `pruned_length`
+/// leaves were pruned.
 ///
-/// Each node contains a hash value. Non-leaf nodes have references to their left
-/// and right children.
-struct Node {
-    hash: [u8; 32],
-    left: Option<Box<Node>>,
-    right: Option<Box<Node>>,
+/// The only node below the frontier that is still needed at a level is the frontier node
+/// itself, which is the left sibling of the first node covering a kept leaf.
+fn level_offset(pruned_length: usize, level: usize) -> usize {
+    (pruned_length >> level) & !1
+}
+
+/// Splits the first `len` bytes off `bytes`, failing if there are fewer.
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], MerkleError> {
+    if bytes.len() < len {
+        return Err(MerkleError::MalformedState("Serialized state is truncated"));
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Splits an 8-byte little-endian integer off `bytes`.
+fn take_usize(bytes: &mut &[u8]) -> Result<usize, MerkleError> {
+    let value = u64::from_le_bytes(take(bytes, 8)?.try_into().unwrap());
+    usize::try_from(value).map_err(|_| MerkleError::MalformedState("Integer is too large"))
 }
 
-/// `Prover` is responsible for constructing a Merkle tree from provided data
-/// and generating proofs for specified leaf indices.
-pub struct Prover {
-    root: Option<Box<Node>>,
+/// `MerkleProver` is responsible for constructing a Merkle tree from provided data
+/// and generating proofs for specified leaf indices, hashing with `H`.
+///
+/// The tree is stored as one flat array of hashes per level, so nodes are found by their
+/// position instead of by following pointers. The last node of an odd-sized level is
+/// promoted to the next level unchanged, unless another `PairingPolicy` is chosen with
+/// `ProverBuilder::pairing`.
+pub struct MerkleProver<H: Hasher> {
+    /// The node hashes level by level, from the leaves at level 0 up to the root.
+    ///
+    /// Level `h` holds the nodes from position `level_offset(pruned_length, h)` onwards.
+    levels: Vec<Vec<[u8; 32]>>,
+    /// The number of leading leaves whose subtrees were pruned by `from_frontier`.
+    pruned_length: usize,
     data_length: usize,
+    /// The original leaf data, kept only by provers created in retaining mode.
+    data: Option<Vec<Vec<u8>>>,
+    /// The salt hashed into every leaf, set only by `ProverBuilder::salt`.
+    salt: Option<Vec<u8>>,
+    /// The maximum number of leaves, `MAX_DATA_SIZE` unless set by `ProverBuilder::max_leaves`.
+    max_leaves: usize,
+    /// How the last node of an odd-sized level is combined, set by `ProverBuilder::pairing`.
+    pairing: PairingPolicy,
+    /// The index of the first leaf with each leaf hash, built by the first `index_of` call
+    /// and cleared whenever the leaves change.
+    leaf_indices: OnceLock<HashMap<[u8; 32], usize>>,
+    _hasher: PhantomData<fn() -> H>,
 }
 
-impl Prover {
+/// A `MerkleProver` hashing with SHA256.
+pub type Prover = MerkleProver<Sha256Hasher>;
+
+impl<H: Hasher> MerkleProver<H> {
     /// Creates a new Prover instance by building a Merkle tree from the provided data.
     ///
     /// This method utilizes a specified number of threads for parallel construction.
     ///
+    /// Empty strings are valid leaves, hashed like any other data; only an empty `data`
+    /// slice is rejected.
+    ///
     /// # Arguments
     ///
     /// * `data` - A slice of string data to construct the Merkle tree.
@@ -41,339 +141,3778 @@ impl Prover {
     ///
     /// # Returns
     ///
-    /// A Result containing the created Prover instance, or an error string if any issues arise.
-    pub fn new(data: &[&str], num_threads: usize) -> Result<Self, &'static str> {
-        if data.is_empty() {
-            return Err("Data cannot be empty");
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn new(data: &[&str], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::builder().data(data).threads(num_threads).build()
+    }
+
+    /// Creates a new Prover instance like `new`, without blocking the async runtime.
+    ///
+    /// The data is copied and the tree is built on tokio's blocking thread pool with
+    /// `spawn_blocking`, so the executor threads stay free during a long construction. It
+    /// must be awaited within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    #[cfg(feature = "tokio")]
+    pub async fn new_async(data: &[&str], num_threads: usize) -> Result<Self, MerkleError>
+    where
+        H: 'static,
+    {
+        let data: Vec<Vec<u8>> = data.iter().map(|d| d.as_bytes().to_vec()).collect();
+        tokio::task::spawn_blocking(move || {
+            let data: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+            Self::from_bytes(&data, num_threads)
+        })
+        .await
+        // The construction does not panic, so the task only fails if the runtime shuts down
+        .map_err(|_| MerkleError::ThreadPool)?
+    }
+
+    /// Returns a `ProverBuilder` for configuring the construction of a Prover.
+    ///
+    /// # Returns
+    ///
+    /// A `ProverBuilder` without data, which must be set with `data` before building, and
+    /// constructing with a single thread.
+    pub fn builder<'a>() -> ProverBuilder<'a, H> {
+        ProverBuilder {
+            data: &[],
+            num_threads: 1,
+            salt: None,
+            retain_data: false,
+            max_leaves: MAX_DATA_SIZE,
+            pairing: PairingPolicy::Promote,
+            _hasher: PhantomData,
         }
-        if data.len() > MAX_DATA_SIZE {
-            return Err("Data size exceeds the maximum allowed size");
+    }
+
+    /// Creates a new Prover instance like `new`, together with a Verifier for its root hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover and Verifier instances, or a `MerkleError` if
+    /// any issues arise.
+    pub fn build_with_verifier(
+        data: &[&str],
+        num_threads: usize,
+    ) -> Result<(Self, MerkleVerifier<H>), MerkleError> {
+        let prover = Self::new(data, num_threads)?;
+        let verifier = MerkleVerifier::new(prover.get_root_hash()?);
+        Ok((prover, verifier))
+    }
+
+    /// Creates a new Prover instance by building a Merkle tree from raw byte data.
+    ///
+    /// Unlike `new`, the leaves do not need to be valid UTF-8.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of byte data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn from_bytes(data: &[&[u8]], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::validate(data.len(), num_threads)?;
+        Ok(Self::from_levels(Self::build_tree(data, num_threads)?))
+    }
+
+    /// Creates a new Prover instance from leaves made of several parts each.
+    ///
+    /// Each leaf is hashed with `Hasher::hash_leaf_parts`, which prefixes every part with
+    /// its length, so leaves holding the same bytes split differently get different hashes.
+    /// Proofs verify as usual, with `leaf_hash` set to the length-prefixed hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaves` - The parts of each leaf, in order.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn from_parts(leaves: &[&[&[u8]]], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::validate(leaves.len(), num_threads)?;
+        let levels = Self::in_thread_pool(num_threads, || {
+            let leaf_hashes: Vec<[u8; 32]> = leaves
+                .par_iter()
+                .map(|parts| H::hash_leaf_parts(parts))
+                .collect();
+            Self::levels_from_leaf_hashes(leaf_hashes)
+        })?;
+        Ok(Self::from_levels(levels))
+    }
+
+    /// Creates a new Prover instance from leaf hashes computed elsewhere.
+    ///
+    /// The hashes are used as the leaf level directly, without hashing them again, and the
+    /// internal nodes are built on top of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `hashes` - The hashes of the leaves, in order.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn from_leaf_hashes(hashes: &[[u8; 32]], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::validate(hashes.len(), num_threads)?;
+        Ok(Self::from_levels(Self::build_tree_from_hashes(
+            hashes.to_vec(),
+            num_threads,
+        )?))
+    }
+
+    /// Creates a new Prover instance from an iterator of owned leaf strings.
+    ///
+    /// Each leaf is hashed as soon as it arrives and then dropped, so only the leaf hashes
+    /// are held in memory, e.g. when reading the leaves from `BufReader::lines()`. The leaves
+    /// are hashed sequentially, and the internal nodes are built in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter` - An iterator over the leaf data, in order.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn from_iter<I: IntoIterator<Item = String>>(
+        iter: I,
+        num_threads: usize,
+    ) -> Result<Self, MerkleError> {
+        let mut hashes = Vec::new();
+        for leaf in iter {
+            if hashes.len() == MAX_DATA_SIZE {
+                return Err(MerkleError::DataTooLarge {
+                    len: MAX_DATA_SIZE + 1,
+                    max: MAX_DATA_SIZE,
+                });
+            }
+            hashes.push(H::hash_leaf(leaf.as_bytes()));
         }
-        if num_threads == 0 {
-            return Err("Number of threads cannot be zero");
+
+        Self::validate(hashes.len(), num_threads)?;
+        Ok(Self::from_levels(Self::build_tree_from_hashes(
+            hashes,
+            num_threads,
+        )?))
+    }
+
+    /// Creates a new Prover instance over arbitrary items, hashing the canonical byte
+    /// encoding `to_bytes` produces for each of them.
+    ///
+    /// The leaves are hashed exactly like the data given to `from_bytes`, so callers control
+    /// serialization without pre-encoding every item.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items to commit to, in order.
+    /// * `to_bytes` - Maps an item to the bytes of its leaf.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn from_items<T: Sync, F: Fn(&T) -> Vec<u8> + Sync>(
+        items: &[T],
+        to_bytes: F,
+        num_threads: usize,
+    ) -> Result<Self, MerkleError> {
+        Self::validate(items.len(), num_threads)?;
+        let levels = Self::in_thread_pool(num_threads, || {
+            let leaf_hashes: Vec<[u8; 32]> = items
+                .par_iter()
+                .map(|item| H::hash_leaf(&to_bytes(item)))
+                .collect();
+            Self::levels_from_leaf_hashes(leaf_hashes)
+        })?;
+        Ok(Self::from_levels(levels))
+    }
+
+    /// Creates a new Prover instance from encoded leaves, committing to their decoded form.
+    ///
+    /// Each leaf is passed through `decode` before it is hashed, so e.g. compressed leaves
+    /// produce the same tree as `from_bytes` on the decompressed data. Proofs for it are
+    /// checked with `Verifier::verify_data_decoded`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of encoded leaf data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    /// * `decode` - Decodes a leaf into the bytes the tree commits to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn with_decode(
+        data: &[&[u8]],
+        num_threads: usize,
+        decode: impl Fn(&[u8]) -> Vec<u8> + Sync,
+    ) -> Result<Self, MerkleError> {
+        Self::validate(data.len(), num_threads)?;
+        let levels = Self::in_thread_pool(num_threads, || {
+            let leaf_hashes: Vec<[u8; 32]> =
+                data.par_iter().map(|d| H::hash_leaf(&decode(d))).collect();
+            Self::levels_from_leaf_hashes(leaf_hashes)
+        })?;
+        Ok(Self::from_levels(levels))
+    }
+
+    /// Creates a new Prover instance like `new`, rejecting data in which any two leaves are
+    /// identical.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` pointing at the
+    /// first leaf that repeats an earlier one, or for any other issue.
+    pub fn new_unique(data: &[&str], num_threads: usize) -> Result<Self, MerkleError> {
+        let mut seen = HashMap::with_capacity(data.len());
+        for (index, leaf) in data.iter().enumerate() {
+            if let Some(&original) = seen.get(leaf.as_bytes()) {
+                return Err(MerkleError::DuplicateLeaf { index, original });
+            }
+            seen.insert(leaf.as_bytes(), index);
         }
-        Ok(Prover {
-            root: Self::build_tree(data, num_threads),
-            data_length: data.len(),
+        Self::new(data, num_threads)
+    }
+
+    /// Creates a new Prover instance, computing the hash of each distinct leaf value and
+    /// each distinct pair of child hashes only once.
+    ///
+    /// Data with long runs of identical leaves produces identical subtrees, whose hashes are
+    /// then taken from a cache keyed by their content. The resulting tree and root hash are
+    /// the same as with `new`. The distinct values of each level are hashed in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn new_deduped(data: &[&str], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::new_deduped_with_count(data, num_threads).map(|(prover, _)| prover)
+    }
+
+    /// Creates a new Prover instance like `new_deduped`, also returning the number of hashes
+    /// that were actually computed.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance and the number of leaf and node hashes
+    /// computed, or a `MerkleError` if any issues arise.
+    pub fn new_deduped_with_count(
+        data: &[&str],
+        num_threads: usize,
+    ) -> Result<(Self, usize), MerkleError> {
+        Self::validate(data.len(), num_threads)?;
+        let data: Vec<&[u8]> = data.iter().map(|d| d.as_bytes()).collect();
+        let (levels, hash_count) =
+            Self::in_thread_pool(num_threads, || Self::build_tree_deduped(&data))?;
+        Ok((Self::from_levels(levels), hash_count))
+    }
+
+    /// Creates a new Prover instance like `from_bytes`, computing the levels of the tree in a
+    /// caller-provided scratch buffer.
+    ///
+    /// Each level is computed into the buffer and then copied into an exactly sized level of
+    /// the tree, so the buffer keeps its allocation. Reusing one buffer across many builds
+    /// avoids growing a fresh buffer for every level. Its contents afterwards are the root
+    /// hash of the new tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of byte data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    /// * `scratch` - The buffer reused for the level computations.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn build_into(
+        data: &[&[u8]],
+        num_threads: usize,
+        scratch: &mut Vec<[u8; 32]>,
+    ) -> Result<Self, MerkleError> {
+        Self::validate(data.len(), num_threads)?;
+        let levels = Self::in_thread_pool(num_threads, || {
+            scratch.clear();
+            scratch.par_extend(data.par_iter().map(|d| H::hash_leaf(d)));
+            let mut levels = vec![scratch.to_vec()];
+
+            while scratch.len() > 1 {
+                let children = levels.last().unwrap();
+                scratch.clear();
+                scratch.par_extend(
+                    children
+                        .par_chunks(2)
+                        .map(|children| Self::parent_hash(children, PairingPolicy::Promote)),
+                );
+                levels.push(scratch.to_vec());
+            }
+            levels
+        })?;
+        Ok(Self::from_levels(levels))
+    }
+
+    /// Creates a new Prover instance whose leaves are bound to a sequence number.
+    ///
+    /// Each leaf is hashed as the 8-byte little-endian `seq` followed by the leaf data, so a
+    /// proof is only accepted by a verifier expecting the same sequence number, see
+    /// `Verifier::verify_sequenced`. This prevents replaying a captured proof later.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    /// * `seq` - The sequence number bound into every leaf.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn new_sequenced(data: &[&str], num_threads: usize, seq: u64) -> Result<Self, MerkleError> {
+        let leaves: Vec<Vec<u8>> = data
+            .iter()
+            .map(|d| [&seq.to_le_bytes()[..], d.as_bytes()].concat())
+            .collect();
+        let leaves: Vec<&[u8]> = leaves.iter().map(Vec::as_slice).collect();
+        Self::from_bytes(&leaves, num_threads)
+    }
+
+    /// Creates a new Prover instance that also retains a copy of the leaf data.
+    ///
+    /// Retaining the data costs memory proportional to its size, but enables proofs that
+    /// embed the leaf data, see `get_proof_inline`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    /// * `num_threads` - The number of threads to be used in the parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn new_retaining(data: &[&str], num_threads: usize) -> Result<Self, MerkleError> {
+        Self::builder()
+            .data(data)
+            .threads(num_threads)
+            .retain_data(true)
+            .build()
+    }
+
+    /// Creates a Prover from the frontier of a tree, i.e. the roots of the perfect subtrees
+    /// covering all of its leaves, as returned by `frontier`.
+    ///
+    /// The subtrees below the frontier are pruned, so the resulting Prover has the same root
+    /// hash as the original tree but cannot generate proofs for the existing leaves. It holds
+    /// the minimal state needed to continue a tree whose nodes were not kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `frontier` - The frontier hashes, ordered from left to right.
+    /// * `size` - The number of leaves in the tree the frontier was taken from.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if the frontier
+    /// does not match `size`.
+    pub fn from_frontier(frontier: &[[u8; 32]], size: usize) -> Result<Self, MerkleError> {
+        Self::from_frontier_with_max(frontier, size, MAX_DATA_SIZE)
+    }
+
+    /// Creates a Prover from a frontier like `from_frontier`, allowing at most `max_leaves`
+    /// leaves.
+    fn from_frontier_with_max(
+        frontier: &[[u8; 32]],
+        size: usize,
+        max_leaves: usize,
+    ) -> Result<Self, MerkleError> {
+        Self::validate_with_max(size, 1, max_leaves)?;
+        if frontier.len() != size.count_ones() as usize {
+            return Err(MerkleError::FrontierMismatch {
+                len: frontier.len(),
+                size,
+            });
+        }
+
+        // Each frontier hash is the last complete node at the level of its height, ordered
+        // from the highest level to the lowest
+        let mut hashes = frontier.iter();
+        let mut levels = vec![Vec::new(); tree_height(size) + 1];
+        for (level, nodes) in levels.iter_mut().enumerate().rev() {
+            if size & (1 << level) != 0 {
+                nodes.push(*hashes.next().unwrap());
+            }
+        }
+
+        // Compute the nodes on the right edge above the frontier
+        Self::rehash_levels(&mut levels, size, size, size, PairingPolicy::Promote);
+
+        Ok(MerkleProver {
+            levels,
+            pruned_length: size,
+            data_length: size,
+            data: None,
+            salt: None,
+            max_leaves,
+            pairing: PairingPolicy::Promote,
+            leaf_indices: OnceLock::new(),
+            _hasher: PhantomData,
         })
     }
 
-    /// Retrieves the hash of the root node of the Merkle tree.
+    /// Checks the construction arguments shared by all constructors.
+    fn validate(data_length: usize, num_threads: usize) -> Result<(), MerkleError> {
+        Self::validate_with_max(data_length, num_threads, MAX_DATA_SIZE)
+    }
+
+    /// Checks the construction arguments against a maximum of `max_leaves` leaves.
+    fn validate_with_max(
+        data_length: usize,
+        num_threads: usize,
+        max_leaves: usize,
+    ) -> Result<(), MerkleError> {
+        if data_length == 0 {
+            return Err(MerkleError::EmptyData);
+        }
+        if data_length > max_leaves {
+            return Err(MerkleError::DataTooLarge {
+                len: data_length,
+                max: max_leaves,
+            });
+        }
+        if num_threads == 0 {
+            return Err(MerkleError::ZeroThreads);
+        }
+        Ok(())
+    }
+
+    /// Wraps the levels of a built tree in a Prover that does not retain the leaf data.
+    fn from_levels(levels: Vec<Vec<[u8; 32]>>) -> Self {
+        MerkleProver {
+            data_length: levels[0].len(),
+            levels,
+            pruned_length: 0,
+            data: None,
+            salt: None,
+            max_leaves: MAX_DATA_SIZE,
+            pairing: PairingPolicy::Promote,
+            leaf_indices: OnceLock::new(),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Appends new leaves to the tree and updates the root.
+    ///
+    /// Subtrees whose leaves are all older than the appended ones are complete and kept as
+    /// they are; only the nodes along the right edge of the tree and above the new leaves are
+    /// rebuilt. Every node recomputed this way would be recomputed by a full rebuild as well,
+    /// so patching is always at least as cheap and the tree is never rebuilt in full. The
+    /// resulting tree is identical to one built from all the data at once. A Prover created
+    /// from a frontier can be appended to as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to append as new leaves.
     ///
     /// # Returns
     ///
-    /// A Result containing the root hash, or an error string if the root is missing.
-    pub fn get_root_hash(&self) -> Result<[u8; 32], &'static str> {
-        self.root
-            .as_ref()
-            .map(|node| node.hash)
-            .ok_or("Root node is missing")
+    /// An empty Result, or a `MerkleError` if the data is empty or the tree would grow
+    /// beyond the maximum allowed size, see `ProverBuilder::max_leaves`.
+    pub fn append(&mut self, data: &[&str]) -> Result<(), MerkleError> {
+        if data.is_empty() {
+            return Err(MerkleError::EmptyData);
+        }
+        // Saturate, so a length beyond usize::MAX is still reported as too large
+        let new_length = self.data_length.saturating_add(data.len());
+        Self::validate_with_max(new_length, 1, self.max_leaves)?;
+
+        let old_length = self.data_length;
+        let leaf_hashes: Vec<[u8; 32]> = data
+            .iter()
+            .map(|d| self.hash_leaf_data(d.as_bytes()))
+            .collect();
+        self.levels[0].extend(leaf_hashes);
+        self.data_length = new_length;
+        self.leaf_indices.take();
+        Self::rehash_levels(
+            &mut self.levels,
+            new_length,
+            self.pruned_length,
+            old_length,
+            self.pairing,
+        );
+
+        if let Some(retained) = &mut self.data {
+            retained.extend(data.iter().map(|d| d.as_bytes().to_vec()));
+        }
+        Ok(())
+    }
+
+    /// Appends the leaves received on `rx` one by one, writing a checkpoint to `path` after
+    /// every `checkpoint_every` appended leaves.
+    ///
+    /// Appending stops once all senders are dropped. After a crash, `from_checkpoint`
+    /// resumes from the last checkpoint, and the leaves received after it must be appended
+    /// again. Leaves appended since the last checkpoint are not written when the channel
+    /// closes; call `write_checkpoint` to persist them on a clean shutdown.
+    ///
+    /// # Arguments
+    ///
+    /// * `rx` - The receiving end of a channel delivering the leaf data in order.
+    /// * `checkpoint_every` - The number of appended leaves between two checkpoints.
+    /// * `path` - The file the checkpoints are written to.
+    ///
+    /// # Returns
+    ///
+    /// An empty Result, or a `MerkleError` if the interval is zero, the tree would grow
+    /// beyond the maximum allowed size or a checkpoint cannot be written.
+    pub fn append_with_checkpointing(
+        &mut self,
+        rx: Receiver<String>,
+        checkpoint_every: usize,
+        path: &Path,
+    ) -> Result<(), MerkleError> {
+        if checkpoint_every == 0 {
+            return Err(MerkleError::ZeroCheckpointInterval);
+        }
+
+        for (appended, leaf) in rx.into_iter().enumerate() {
+            self.append(&[&leaf])?;
+            if (appended + 1).is_multiple_of(checkpoint_every) {
+                self.write_checkpoint(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the leaf count and frontier of the tree to `path`, enough to resume it with
+    /// `from_checkpoint`.
+    ///
+    /// The checkpoint is the leaf count and the maximum number of leaves, each as 8 bytes
+    /// little-endian, followed by the frontier hashes. It is written to a temporary file first and then renamed, so a crash while
+    /// writing leaves the previous checkpoint intact.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write the checkpoint to.
+    ///
+    /// # Returns
+    ///
+    /// An empty Result, or a `MerkleError` if the file cannot be written.
+    pub fn write_checkpoint(&self, path: &Path) -> Result<(), MerkleError> {
+        // A checkpoint resumes with `from_frontier`, which promotes
+        self.require_promotion()?;
+        let mut bytes = (self.data_length as u64).to_le_bytes().to_vec();
+        bytes.extend((self.max_leaves as u64).to_le_bytes());
+        bytes.extend(self.frontier().concat());
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, bytes)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Creates a Prover from a checkpoint written by `write_checkpoint`.
+    ///
+    /// Like with `from_frontier`, the existing leaves are pruned, and new leaves can be
+    /// appended up to the maximum number of leaves of the checkpointed tree. Checkpoints
+    /// without a maximum, written by earlier versions, allow `MAX_DATA_SIZE` leaves.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The checkpoint file.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the resumed Prover instance, or a `MerkleError` if the file cannot
+    /// be read or is not a valid checkpoint.
+    pub fn from_checkpoint(path: &Path) -> Result<Self, MerkleError> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < 8 {
+            return Err(MerkleError::MalformedCheckpoint("Checkpoint is too short"));
+        }
+        let read_usize = |bytes: &[u8]| {
+            usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+                .map_err(|_| MerkleError::MalformedCheckpoint("Tree size is too large"))
+        };
+        let (size_bytes, rest) = bytes.split_at(8);
+        let size = read_usize(size_bytes)?;
+
+        // The frontier is a whole number of hashes, so the length tells whether a maximum
+        // precedes it
+        let (max_leaves, frontier_bytes) = if rest.len().is_multiple_of(32) {
+            (MAX_DATA_SIZE, rest)
+        } else if rest.len() >= 8 && (rest.len() - 8).is_multiple_of(32) {
+            let (max_bytes, frontier_bytes) = rest.split_at(8);
+            (read_usize(max_bytes)?, frontier_bytes)
+        } else {
+            return Err(MerkleError::MalformedCheckpoint(
+                "Frontier is not a whole number of hashes",
+            ));
+        };
+
+        let frontier: Vec<[u8; 32]> = frontier_bytes
+            .chunks_exact(32)
+            .map(|hash| hash.try_into().unwrap())
+            .collect();
+        Self::from_frontier_with_max(&frontier, size, max_leaves)
+    }
+
+    /// Serializes the full tree state, so the tree can be restored with `deserialize`
+    /// without being rebuilt.
+    ///
+    /// The state is written in the following format, with integers as 8 bytes little-endian:
+    ///
+    /// 1. The magic bytes `MKTS` and a version byte, currently `3`.
+    /// 2. The number of leaves, the number of leaves pruned by `from_frontier` and the
+    ///    maximum number of leaves. States before version `3` have no maximum and allow
+    ///    `MAX_DATA_SIZE` leaves.
+    /// 3. The kept node hashes level by level, from the leaves up to the root.
+    /// 4. A byte that is `1` if the leaf data is retained, followed by the length and bytes of
+    ///    each leaf, or `0` otherwise.
+    /// 5. A byte that is `1` if the leaves are salted, followed by the length and bytes of the
+    ///    salt, or `0` otherwise.
+    /// 6. The pairing policy: `0` to promote, `1` to duplicate and `2` to hash with zero.
+    ///    States of version `1` have no such byte and promote.
+    /// 7. The hash of everything before it, to detect corrupted input.
+    ///
+    /// # Returns
+    ///
+    /// The serialized tree state.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.push(STATE_VERSION);
+        bytes.extend((self.data_length as u64).to_le_bytes());
+        bytes.extend((self.pruned_length as u64).to_le_bytes());
+        bytes.extend((self.max_leaves as u64).to_le_bytes());
+        for level in &self.levels {
+            bytes.extend(level.concat());
+        }
+        match &self.data {
+            Some(data) => {
+                bytes.push(1);
+                for leaf in data {
+                    bytes.extend((leaf.len() as u64).to_le_bytes());
+                    bytes.extend(leaf);
+                }
+            }
+            None => bytes.push(0),
+        }
+        match &self.salt {
+            Some(salt) => {
+                bytes.push(1);
+                bytes.extend((salt.len() as u64).to_le_bytes());
+                bytes.extend(salt);
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(match self.pairing {
+            PairingPolicy::Promote => 0,
+            PairingPolicy::Duplicate => 1,
+            PairingPolicy::HashWithZero => 2,
+        });
+
+        let checksum = H::hash(&[&bytes]);
+        bytes.extend(checksum);
+        bytes
+    }
+
+    /// Restores a Prover from a tree state written by `serialize`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The serialized tree state.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the restored Prover instance, or a `MerkleError` if the state is
+    /// corrupted or was not written by `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, MerkleError> {
+        if bytes.len() < 32 {
+            return Err(MerkleError::MalformedState("Serialized state is truncated"));
+        }
+        let (mut rest, checksum) = bytes.split_at(bytes.len() - 32);
+        if H::hash(&[rest]) != checksum {
+            return Err(MerkleError::MalformedState("Checksum does not match"));
+        }
+
+        if take(&mut rest, STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(MerkleError::MalformedState("Missing magic bytes"));
+        }
+        let version = take(&mut rest, 1)?[0];
+        if !(1..=STATE_VERSION).contains(&version) {
+            return Err(MerkleError::MalformedState("Unsupported version"));
+        }
+        let data_length = take_usize(&mut rest)?;
+        let pruned_length = take_usize(&mut rest)?;
+        let max_leaves = match version {
+            1 | 2 => MAX_DATA_SIZE,
+            _ => take_usize(&mut rest)?,
+        };
+        Self::validate_with_max(data_length, 1, max_leaves)
+            .map_err(|_| MerkleError::MalformedState("Invalid tree size"))?;
+        if pruned_length > data_length {
+            return Err(MerkleError::MalformedState(
+                "Pruned length exceeds the tree size",
+            ));
+        }
+
+        let mut levels = Vec::with_capacity(tree_height(data_length) + 1);
+        for level in 0..=tree_height(data_length) {
+            let node_count = level_width(data_length, level) - level_offset(pruned_length, level);
+            let nodes: Vec<[u8; 32]> = take(&mut rest, 32 * node_count)?
+                .chunks_exact(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect();
+            levels.push(nodes);
+        }
+
+        let data = match take(&mut rest, 1)? {
+            [0] => None,
+            [1] => Some(
+                (0..data_length)
+                    .map(|_| {
+                        let len = take_usize(&mut rest)?;
+                        Ok(take(&mut rest, len)?.to_vec())
+                    })
+                    .collect::<Result<Vec<_>, MerkleError>>()?,
+            ),
+            _ => return Err(MerkleError::MalformedState("Invalid retained data flag")),
+        };
+        let salt = match take(&mut rest, 1)? {
+            [0] => None,
+            [1] => {
+                let len = take_usize(&mut rest)?;
+                Some(take(&mut rest, len)?.to_vec())
+            }
+            _ => return Err(MerkleError::MalformedState("Invalid salt flag")),
+        };
+        let pairing = match version {
+            1 => PairingPolicy::Promote,
+            _ => match take(&mut rest, 1)? {
+                [0] => PairingPolicy::Promote,
+                [1] => PairingPolicy::Duplicate,
+                [2] => PairingPolicy::HashWithZero,
+                _ => return Err(MerkleError::MalformedState("Invalid pairing policy")),
+            },
+        };
+        if !rest.is_empty() {
+            return Err(MerkleError::MalformedState(
+                "Trailing bytes after the state",
+            ));
+        }
+
+        Ok(MerkleProver {
+            levels,
+            pruned_length,
+            data_length,
+            data,
+            salt,
+            max_leaves,
+            pairing,
+            leaf_indices: OnceLock::new(),
+            _hasher: PhantomData,
+        })
+    }
+
+    /// Replaces the data of a single leaf and recomputes the root hash.
+    ///
+    /// Only the leaf and its ancestors are rehashed, so an update costs O(log n) hash
+    /// operations instead of a full rebuild.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf to update.
+    /// * `new_data` - The new data of the leaf.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new root hash, or a `MerkleError` if the leaf index is out
+    /// of bounds or the leaf is pruned from the tree.
+    pub fn update_leaf(
+        &mut self,
+        leaf_index: usize,
+        new_data: &str,
+    ) -> Result<[u8; 32], MerkleError> {
+        if leaf_index >= self.data_length {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.data_length,
+            });
+        }
+
+        // Leaves left of the frontier of a Prover built from a frontier are not kept
+        let leaf_offset = level_offset(self.pruned_length, 0);
+        if leaf_index < leaf_offset {
+            return Err(MerkleError::LeafPruned { index: leaf_index });
+        }
+
+        self.levels[0][leaf_index - leaf_offset] = self.hash_leaf_data(new_data.as_bytes());
+        self.leaf_indices.take();
+        for level in 0..self.tree_height() {
+            let first_child = (leaf_index >> level) & !1;
+            let children =
+                &self.levels[level][first_child - level_offset(self.pruned_length, level)..];
+            let parent_hash = Self::parent_hash(&children[..children.len().min(2)], self.pairing);
+
+            let parent = (leaf_index >> (level + 1)) - level_offset(self.pruned_length, level + 1);
+            self.levels[level + 1][parent] = parent_hash;
+        }
+
+        if let Some(retained) = &mut self.data {
+            retained[leaf_index] = new_data.as_bytes().to_vec();
+        }
+        self.get_root_hash()
+    }
+
+    /// Computes the root hash the tree would have if a leaf were replaced, without modifying
+    /// the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf to replace.
+    /// * `new_data` - The hypothetical new value of the leaf.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the hypothetical root hash, or a `MerkleError` if the leaf index is
+    /// out of bounds or the leaf has been pruned.
+    pub fn root_if_changed(
+        &self,
+        leaf_index: usize,
+        new_data: &str,
+    ) -> Result<[u8; 32], MerkleError> {
+        if leaf_index >= self.data_length {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.data_length,
+            });
+        }
+        if leaf_index < level_offset(self.pruned_length, 0) {
+            return Err(MerkleError::LeafPruned { index: leaf_index });
+        }
+
+        let mut node = self.hash_leaf_data(new_data.as_bytes());
+        for level in 0..self.tree_height() {
+            let position = leaf_index >> level;
+            // The hypothetical node is promoted unchanged when it has no sibling, unless the
+            // pairing policy pairs it with a stand-in
+            let sibling = self
+                .node_at(level, position ^ 1)
+                .or_else(|| self.pairing.lone_sibling(&node));
+            if let Some(sibling) = sibling {
+                node = if position.is_multiple_of(2) {
+                    H::hash_node(&node, &sibling)
+                } else {
+                    H::hash_node(&sibling, &node)
+                };
+            }
+        }
+        Ok(node)
+    }
+
+    /// Retrieves the hash of the root node of the Merkle tree.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the root hash, or a `MerkleError` if the root is missing.
+    pub fn get_root_hash(&self) -> Result<[u8; 32], MerkleError> {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .ok_or(MerkleError::MissingRoot)
+    }
+
+    /// Returns the hash of the root node of the Merkle tree.
+    ///
+    /// Every constructor rejects empty data, so a built tree always has a root and, unlike
+    /// `get_root_hash`, this cannot fail.
+    pub fn root(&self) -> [u8; 32] {
+        self.get_root_hash().expect("Root node is always present")
+    }
+
+    /// Returns the hash of the node at the given level and position of the tree.
+    ///
+    /// Level 0 holds the leaves and level `tree_height` holds the root. The last node of an
+    /// odd-sized level is promoted, so its hash also appears at the next level.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level of the node, counted from the leaves.
+    /// * `position` - The position of the node within its level, counted from the left.
+    ///
+    /// # Returns
+    ///
+    /// The hash of the node, or `None` if the coordinates lie outside the tree or the node is
+    /// pruned by `from_frontier`.
+    pub fn node_hash(&self, level: usize, position: usize) -> Option<[u8; 32]> {
+        self.node_at(level, position)
+    }
+
+    /// Extracts the subtree rooted at an internal node as a standalone Prover, e.g. to hand a
+    /// shard of the leaves to a worker.
+    ///
+    /// The node at `level` and `position` covers the leaves from `position << level` up to
+    /// `(position + 1) << level` or the end of the tree. The returned Prover holds copies of
+    /// the nodes below it, so its root hash is the hash of that node and its proofs verify
+    /// against it. Leaf indices in the subtree start at zero. The salt, retained data, pairing
+    /// policy and maximum size of the tree carry over.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level of the node, counted from the leaves.
+    /// * `position` - The position of the node within its level, counted from the left.
+    ///
+    /// # Returns
+    ///
+    /// The subtree Prover, or `None` if there is no such node, a node below it is pruned, or
+    /// the node covers fewer than `1 << level` leaves in a tree that does not promote, so
+    /// its hash involves stand-in siblings outside the subtree.
+    pub fn subtree(&self, level: usize, position: usize) -> Option<Self> {
+        if level > self.tree_height() || position >= level_width(self.data_length, level) {
+            return None;
+        }
+        let first_leaf = position << level;
+        let leaf_count = (self.data_length - first_leaf).min(1 << level);
+        if leaf_count < 1 << level && self.pairing != PairingPolicy::Promote {
+            return None;
+        }
+
+        let levels = (0..=tree_height(leaf_count))
+            .map(|height| {
+                let start =
+                    (first_leaf >> height).checked_sub(level_offset(self.pruned_length, height))?;
+                let end = start + level_width(leaf_count, height);
+                self.levels[height].get(start..end).map(<[_]>::to_vec)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut subtree = Self::from_levels(levels);
+        subtree.data = self
+            .data
+            .as_ref()
+            .map(|data| data[first_leaf..first_leaf + leaf_count].to_vec());
+        subtree.salt.clone_from(&self.salt);
+        subtree.max_leaves = self.max_leaves;
+        subtree.pairing = self.pairing;
+        Some(subtree)
+    }
+
+    /// Computes a commitment binding the root hash to a metadata blob, e.g. a schema version
+    /// and timestamp published alongside the root.
+    ///
+    /// The commitment is the hash of the root hash followed by `meta`, so neither can be
+    /// replaced without changing it. See `Verifier::verify_proof_with_meta`.
+    ///
+    /// # Arguments
+    ///
+    /// * `meta` - The metadata to bind to the root.
+    ///
+    /// # Returns
+    ///
+    /// The commitment over the root hash and the metadata.
+    pub fn committed_root_with_meta(&self, meta: &[u8]) -> [u8; 32] {
+        H::hash(&[&self.root(), meta])
+    }
+
+    /// Returns the number of leaves in the tree.
+    pub fn leaf_count(&self) -> usize {
+        self.data_length
+    }
+
+    /// Retrieves the leaf hashes of the tree in insertion order.
+    ///
+    /// The leaves of a Prover created with `from_frontier` are pruned, so only the leaves
+    /// appended after it was created are returned.
+    ///
+    /// # Returns
+    ///
+    /// A vector with the hash of each retained leaf, ordered by leaf index.
+    pub fn leaf_hashes(&self) -> Vec<[u8; 32]> {
+        let leaf_offset = level_offset(self.pruned_length, 0);
+        self.levels[0][self.pruned_length - leaf_offset..].to_vec()
+    }
+
+    /// Returns the retained data of a leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the leaf.
+    ///
+    /// # Returns
+    ///
+    /// The data of the leaf, or `None` if the data is not retained, the index is out of
+    /// bounds, or the data is not valid UTF-8.
+    pub fn leaf_data(&self, index: usize) -> Option<&str> {
+        let data = self.data.as_ref()?.get(index)?;
+        std::str::from_utf8(data).ok()
+    }
+
+    /// Returns the height of the tree, which is also the longest authentication path length of
+    /// its proofs.
+    pub fn height(&self) -> usize {
+        self.tree_height()
+    }
+
+    /// Predicts the authentication path length of the proofs of this tree without generating
+    /// one, e.g. to size buffers.
+    ///
+    /// This is the tree height, which the path of every leaf under a complete subtree has.
+    /// Leaves under a promoted node have shorter paths, so it is an upper bound for them, see
+    /// `path_len_of` for the exact length of a given leaf.
+    pub fn proof_len_for(&self) -> usize {
+        self.tree_height()
+    }
+
+    /// Returns the exact authentication path length of the proof of a leaf without
+    /// generating it.
+    ///
+    /// With promotion, a leaf under a promoted node has a shorter path than the tree height.
+    /// With other pairing policies every path has the length of the tree height.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf whose proof is sized.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the number of path entries of the proof, or a `MerkleError` if the
+    /// index is out of bounds.
+    pub fn path_len_of(&self, leaf_index: usize) -> Result<usize, MerkleError> {
+        if leaf_index >= self.data_length {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.data_length,
+            });
+        }
+        Ok(match self.pairing {
+            PairingPolicy::Promote => path_length(leaf_index, self.data_length),
+            _ => self.tree_height(),
+        })
+    }
+
+    /// Generates a Merkle proof for the specified leaf index.
+    ///
+    /// In a tree of a single leaf, the leaf hash is the root hash, so the proof of that leaf
+    /// has an empty authentication path.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf for which the proof should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MerkleProof, or a `MerkleError` if any issues arise.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        if leaf_index >= self.data_length {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.data_length,
+            });
+        }
+
+        // Nodes left of the frontier of a Prover built from a frontier are not kept
+        let leaf_hash = self
+            .node_at(0, leaf_index)
+            .ok_or(MerkleError::LeafPruned { index: leaf_index })?;
+
+        // A single leaf is the root itself and has no siblings
+        if self.data_length == 1 {
+            return Ok(MerkleProof {
+                leaf_index,
+                tree_size: 1,
+                leaf_hash,
+                authentication_path: Vec::new(),
+                leaf_data: None,
+            });
+        }
+
+        let mut authentication_path = Vec::new();
+        for level in 0..self.tree_height() {
+            let sibling = (leaf_index >> level) ^ 1;
+            if sibling < level_width(self.data_length, level) {
+                let sibling_hash = self
+                    .node_at(level, sibling)
+                    .ok_or(MerkleError::LeafPruned { index: leaf_index })?;
+                authentication_path.push(sibling_hash);
+            } else {
+                // The last node of an odd-sized level has no sibling, and is either promoted
+                // or paired with a stand-in
+                let node = self
+                    .node_at(level, sibling ^ 1)
+                    .ok_or(MerkleError::LeafPruned { index: leaf_index })?;
+                authentication_path.extend(self.pairing.lone_sibling(&node));
+            }
+        }
+        // The authentication path is ordered from the root down to the leaf
+        authentication_path.reverse();
+
+        Ok(MerkleProof {
+            leaf_index,
+            tree_size: self.data_length,
+            leaf_hash,
+            authentication_path,
+            leaf_data: None,
+        })
+    }
+
+    /// Generates a Merkle proof for the specified leaf index, bundled with the root hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf for which the proof should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated SelfContainedProof, or a `MerkleError` if any issues
+    /// arise.
+    pub fn get_self_contained_proof(
+        &self,
+        leaf_index: usize,
+    ) -> Result<SelfContainedProof, MerkleError> {
+        Ok(SelfContainedProof {
+            proof: self.get_proof(leaf_index)?,
+            root: self.get_root_hash()?,
+        })
+    }
+
+    /// Generates Merkle proofs for several leaf indices in one call.
+    ///
+    /// All indices are validated before any proof is generated, so an out-of-bounds index
+    /// fails the whole call without partial results. Large batches are generated in parallel
+    /// on the global rayon thread pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_indices` - The indices of the leaves for which proofs should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the proofs in the same order as `leaf_indices`, or a `MerkleError`
+    /// if any index is out of bounds.
+    pub fn get_proofs(&self, leaf_indices: &[usize]) -> Result<Vec<MerkleProof>, MerkleError> {
+        if let Some(&index) = leaf_indices
+            .iter()
+            .find(|&&leaf_index| leaf_index >= self.data_length)
+        {
+            return Err(MerkleError::IndexOutOfBounds {
+                index,
+                len: self.data_length,
+            });
+        }
+
+        // The tree is only read, so the proofs can be generated on the global rayon pool
+        if leaf_indices.len() >= PARALLEL_PROOFS_THRESHOLD {
+            return leaf_indices
+                .par_iter()
+                .map(|&leaf_index| self.get_proof(leaf_index))
+                .collect();
+        }
+
+        leaf_indices
+            .iter()
+            .map(|&leaf_index| self.get_proof(leaf_index))
+            .collect()
+    }
+
+    /// Returns an iterator over the proofs of all leaves, in order of their leaf index.
+    ///
+    /// Each proof is generated when the iterator is advanced, so the whole proof set is never
+    /// held in memory. Leaves pruned by `from_frontier` cannot be proven and are skipped.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the MerkleProof of each leaf.
+    pub fn proofs(&self) -> impl Iterator<Item = MerkleProof> + '_ {
+        (0..self.data_length).filter_map(|leaf_index| self.get_proof(leaf_index).ok())
+    }
+
+    /// Generates a single proof of inclusion for several leaves.
+    ///
+    /// Internal nodes shared by the authentication paths of the leaves are included only
+    /// once, and nodes that can be computed from the proven leaves are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_indices` - The indices of the leaves to prove, in any order.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MultiProof, or a `MerkleError` if any issues arise.
+    pub fn get_multiproof(&self, leaf_indices: &[usize]) -> Result<MultiProof, MerkleError> {
+        self.require_promotion()?;
+        if leaf_indices.is_empty() {
+            return Err(MerkleError::EmptyIndices);
+        }
+        if let Some(&index) = leaf_indices
+            .iter()
+            .find(|&&leaf_index| leaf_index >= self.data_length)
+        {
+            return Err(MerkleError::IndexOutOfBounds {
+                index,
+                len: self.data_length,
+            });
+        }
+
+        let mut leaf_indices = leaf_indices.to_vec();
+        leaf_indices.sort_unstable();
+        leaf_indices.dedup();
+
+        let leaf_hashes = leaf_indices
+            .iter()
+            .map(|&leaf_index| self.node_at(0, leaf_index))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MerkleError::NodePruned)?;
+
+        // Walk up the tree, collecting the siblings of the known nodes that are not known themselves
+        let mut sibling_hashes = Vec::new();
+        let mut positions = leaf_indices.clone();
+        let mut width = self.data_length;
+        for level in 0..self.tree_height() {
+            let mut i = 0;
+            while i < positions.len() {
+                let position = positions[i];
+                if position.is_multiple_of(2) && positions.get(i + 1) == Some(&(position + 1)) {
+                    i += 2;
+                } else if position ^ 1 >= width {
+                    // The last node of an odd-sized level is promoted without a sibling
+                    i += 1;
+                } else {
+                    let sibling_hash = self
+                        .node_at(level, position ^ 1)
+                        .ok_or(MerkleError::NodePruned)?;
+                    sibling_hashes.push(sibling_hash);
+                    i += 1;
+                }
+            }
+
+            positions = positions.iter().map(|position| position / 2).collect();
+            positions.dedup();
+            width = width.div_ceil(2);
+        }
+
+        Ok(MultiProof {
+            tree_size: self.data_length,
+            leaf_indices,
+            leaf_hashes,
+            sibling_hashes,
+        })
+    }
+
+    /// Lists the leaves that differ between this tree and another tree of the same size.
+    ///
+    /// Both trees are descended from their roots, and subtrees whose root hashes are equal
+    /// are skipped, so only O(changes * log n) nodes are compared.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree to compare with.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the sorted indices of the differing leaves, or a `MerkleError` if
+    /// the trees differ in size or a node to compare was pruned by `from_frontier`.
+    pub fn diff(&self, other: &Self) -> Result<Vec<usize>, MerkleError> {
+        if self.data_length != other.data_length {
+            return Err(MerkleError::TreeSizeMismatch {
+                len: self.data_length,
+                other: other.data_length,
+            });
+        }
+
+        let mut changed = Vec::new();
+        // Visit the nodes depth first, left before right, so the leaves come out sorted
+        let mut stack = vec![(self.levels.len() - 1, 0)];
+        while let Some((level, position)) = stack.pop() {
+            let node = self
+                .node_at(level, position)
+                .ok_or(MerkleError::NodePruned)?;
+            let other_node = other
+                .node_at(level, position)
+                .ok_or(MerkleError::NodePruned)?;
+            if node == other_node {
+                continue;
+            }
+            if level == 0 {
+                changed.push(position);
+                continue;
+            }
+            // A promoted node has no right child
+            if 2 * position + 1 < level_width(self.data_length, level - 1) {
+                stack.push((level - 1, 2 * position + 1));
+            }
+            stack.push((level - 1, 2 * position));
+        }
+        Ok(changed)
+    }
+
+    /// Generates a single proof of inclusion for the contiguous leaves `[start, end)`.
+    ///
+    /// Only the siblings on the two boundaries of the range are included, so the proof grows
+    /// with the height of the tree rather than with the number of proven leaves.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The index of the first leaf to prove.
+    /// * `end` - The index after the last leaf to prove.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated RangeProof, or a `MerkleError` if the range is empty
+    /// or extends beyond the tree.
+    pub fn range_proof(&self, start: usize, end: usize) -> Result<RangeProof, MerkleError> {
+        self.require_promotion()?;
+        if start >= end {
+            return Err(MerkleError::EmptyRange { start, end });
+        }
+        if end > self.data_length {
+            return Err(MerkleError::SizeOutOfBounds {
+                size: end,
+                len: self.data_length,
+            });
+        }
+
+        let leaf_hashes = (start..end)
+            .map(|leaf_index| self.node_at(0, leaf_index))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MerkleError::NodePruned)?;
+
+        // Walk up the tree, collecting the siblings just outside the range at each level
+        let mut sibling_hashes = Vec::new();
+        let (mut low, mut high, mut width) = (start, end, self.data_length);
+        for level in 0..self.tree_height() {
+            if low % 2 == 1 {
+                low -= 1;
+                sibling_hashes.push(self.node_at(level, low).ok_or(MerkleError::NodePruned)?);
+            }
+            // The last node of an odd-sized level is promoted without a sibling
+            if high % 2 == 1 && high < width {
+                sibling_hashes.push(self.node_at(level, high).ok_or(MerkleError::NodePruned)?);
+                high += 1;
+            }
+            low /= 2;
+            high = high.div_ceil(2);
+            width = width.div_ceil(2);
+        }
+
+        Ok(RangeProof {
+            tree_size: self.data_length,
+            start,
+            leaf_hashes,
+            sibling_hashes,
+        })
+    }
+
+    /// Generates a proof that this tree extends the tree over its first `old_size` leaves.
+    ///
+    /// The proof holds the minimal set of subtree hashes from which both the old and the
+    /// current root hash can be computed, following RFC 6962. It is checked with
+    /// `Verifier::verify_consistency`.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_size` - The number of leaves in the old tree. Must be between 1 and the number
+    ///   of leaves.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated ConsistencyProof, or a `MerkleError` if `old_size`
+    /// is out of range or a needed subtree is pruned.
+    pub fn consistency_proof(&self, old_size: usize) -> Result<ConsistencyProof, MerkleError> {
+        self.require_promotion()?;
+        if old_size == 0 || old_size > self.data_length {
+            return Err(MerkleError::SizeOutOfBounds {
+                size: old_size,
+                len: self.data_length,
+            });
+        }
+
+        let mut hashes = Vec::new();
+        self.consistency_subproof(0, self.data_length, old_size, true, &mut hashes)?;
+
+        Ok(ConsistencyProof {
+            old_size,
+            new_size: self.data_length,
+            hashes,
+        })
+    }
+
+    /// Appends the RFC 6962 `SUBPROOF` of the first `old_size` leaves of the subtree over the
+    /// leaves `[start, end)` to `hashes`.
+    ///
+    /// `whole` is true while the old tree is the whole left part of the tree, in which case
+    /// the verifier knows its root already and it is left out.
+    fn consistency_subproof(
+        &self,
+        start: usize,
+        end: usize,
+        old_size: usize,
+        whole: bool,
+        hashes: &mut Vec<[u8; 32]>,
+    ) -> Result<(), MerkleError> {
+        if old_size == end - start {
+            if !whole {
+                hashes.push(self.subtree_hash(start, end)?);
+            }
+            return Ok(());
+        }
+
+        // The largest power of two smaller than the subtree splits it into two subtrees
+        let split = 1 << (tree_height(end - start) - 1);
+        if old_size <= split {
+            self.consistency_subproof(start, start + split, old_size, whole, hashes)?;
+            hashes.push(self.subtree_hash(start + split, end)?);
+        } else {
+            self.consistency_subproof(start + split, end, old_size - split, false, hashes)?;
+            hashes.push(self.subtree_hash(start, start + split)?);
+        }
+        Ok(())
+    }
+
+    /// Returns the hash of the subtree over the leaves `[start, end)`.
+    ///
+    /// The subtree must be a node of the tree: `start` is a multiple of the smallest power of
+    /// two not below `end - start`, and `end` is either that far from `start` or the last leaf.
+    fn subtree_hash(&self, start: usize, end: usize) -> Result<[u8; 32], MerkleError> {
+        let level = tree_height(end - start);
+        self.node_at(level, start >> level)
+            .ok_or(MerkleError::NodePruned)
+    }
+
+    /// Generates a Merkle proof for the specified leaf index with the leaf data embedded.
+    ///
+    /// The resulting proof is self-contained and can be checked with
+    /// `Verifier::verify_inline_proof`. Requires a Prover created with `new_retaining`.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf for which the proof should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MerkleProof, or a `MerkleError` if any issues arise.
+    pub fn get_proof_inline(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        let data = self.data.as_ref().ok_or(MerkleError::DataNotRetained)?;
+        let mut proof = self.get_proof(leaf_index)?;
+        proof.leaf_data = Some(data[leaf_index].clone());
+        Ok(proof)
+    }
+
+    /// Generates a Merkle proof for the specified leaf index together with the leaf data.
+    ///
+    /// Requires a Prover created with `new_retaining`.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_index` - The index of the leaf for which the proof should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MerkleProof and the original leaf string, or a
+    /// `MerkleError` if any issues arise.
+    pub fn get_proof_with_data(
+        &self,
+        leaf_index: usize,
+    ) -> Result<(MerkleProof, String), MerkleError> {
+        let data = self.data.as_ref().ok_or(MerkleError::DataNotRetained)?;
+        let proof = self.get_proof(leaf_index)?;
+        let leaf = String::from_utf8(data[leaf_index].clone())
+            .map_err(|_| MerkleError::InvalidUtf8 { index: leaf_index })?;
+        Ok((proof, leaf))
+    }
+
+    /// Generates a Merkle proof for the leaf holding the given data.
+    ///
+    /// The leaves are searched from left to right by comparing leaf hashes, so if the data
+    /// occurs more than once the proof is for its first occurrence.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The data of the leaf for which the proof should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the generated MerkleProof, or a `MerkleError` if no leaf holds
+    /// the data.
+    pub fn generate_proof(&self, target: &str) -> Result<MerkleProof, MerkleError> {
+        let leaf_index = self.index_of(target).ok_or(MerkleError::DataNotFound)?;
+
+        self.get_proof(leaf_index)
+    }
+
+    /// Returns the index of the first leaf holding the given data.
+    ///
+    /// Leaves are looked up by their hash, so the data does not have to be retained. The
+    /// first call builds a map from each leaf hash to the index of its first leaf, which
+    /// later calls reuse until the leaves change. Leaves left of the frontier of a Prover
+    /// created with `from_frontier` are not searched.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the leaf to look up.
+    ///
+    /// # Returns
+    ///
+    /// The index of the first leaf holding `data`, or `None` if no leaf holds it.
+    pub fn index_of(&self, data: &str) -> Option<usize> {
+        let leaf_indices = self.leaf_indices.get_or_init(|| {
+            let first_kept = level_offset(self.pruned_length, 0);
+            let mut leaf_indices = HashMap::with_capacity(self.levels[0].len());
+            for (offset, leaf_hash) in self.levels[0].iter().enumerate() {
+                leaf_indices
+                    .entry(*leaf_hash)
+                    .or_insert(first_kept + offset);
+            }
+            leaf_indices
+        });
+        leaf_indices
+            .get(&self.hash_leaf_data(data.as_bytes()))
+            .copied()
+    }
+
+    /// Returns the roots of the perfect subtrees that together cover the leaf prefix `[0, k)`.
+    ///
+    /// The subtrees are ordered from left to right, so their heights are the set bits of `k`
+    /// from the most to the least significant. This compact range is enough to recompute the
+    /// root of a tree built over the first `k` leaves, see `Verifier::compact_range_root`.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The length of the prefix to cover. Must be between 1 and the number of leaves.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the subtree roots, or a `MerkleError` if `k` is out of range.
+    pub fn compact_range(&self, k: usize) -> Result<Vec<[u8; 32]>, MerkleError> {
+        self.require_promotion()?;
+        if k == 0 || k > self.data_length {
+            return Err(MerkleError::SizeOutOfBounds {
+                size: k,
+                len: self.data_length,
+            });
+        }
+
+        let mut range = Vec::with_capacity(k.count_ones() as usize);
+        let mut start = 0;
+        for level in (0..usize::BITS as usize).rev() {
+            if k & (1 << level) != 0 {
+                let hash = self
+                    .node_at(level, start >> level)
+                    .ok_or(MerkleError::NodePruned)?;
+                range.push(hash);
+                start += 1 << level;
+            }
+        }
+
+        Ok(range)
+    }
+
+    /// Returns the frontier of the tree: the roots of the perfect subtrees covering all leaves.
+    ///
+    /// Together with the number of leaves, the frontier is enough to recompute the root hash,
+    /// see `from_frontier`.
+    ///
+    /// # Returns
+    ///
+    /// The frontier hashes ordered from left to right, one for each set bit of the leaf count.
+    pub fn frontier(&self) -> Vec<[u8; 32]> {
+        self.compact_range(self.data_length)
+            .expect("Frontier nodes are always present")
+    }
+
+    /// Computes the hash of a leaf holding `data`, with the salt of the tree if it has one.
+    fn hash_leaf_data(&self, data: &[u8]) -> [u8; 32] {
+        match &self.salt {
+            Some(salt) => H::hash_salted_leaf(salt, data),
+            None => H::hash_leaf(data),
+        }
+    }
+
+    /// Fails unless the tree promotes the last node of odd-sized levels, for the operations
+    /// that depend on it.
+    fn require_promotion(&self) -> Result<(), MerkleError> {
+        if self.pairing != PairingPolicy::Promote {
+            return Err(MerkleError::PairingNotSupported);
+        }
+        Ok(())
+    }
+
+    /// Returns the height of the tree, i.e. the number of edges from the root to a leaf.
+    fn tree_height(&self) -> usize {
+        tree_height(self.data_length)
+    }
+
+    /// Returns the hash of the node at the given level and position, where level 0 holds the
+    /// leaves.
+    ///
+    /// Positions beyond the end of a level and nodes pruned by `from_frontier` yield `None`.
+    fn node_at(&self, level: usize, position: usize) -> Option<[u8; 32]> {
+        let index = position.checked_sub(level_offset(self.pruned_length, level))?;
+        self.levels.get(level)?.get(index).copied()
+    }
+
+    /// Runs `op` on a rayon thread pool of `num_threads` threads, so the parallel
+    /// construction inside it uses at most that many threads.
+    fn in_thread_pool<T: Send>(
+        num_threads: usize,
+        op: impl FnOnce() -> T + Send,
+    ) -> Result<T, MerkleError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|_| MerkleError::ThreadPool)?;
+        Ok(pool.install(op))
+    }
+
+    /// Constructs the Merkle tree from the provided data.
+    ///
+    /// Internal method used during the creation of the Prover instance. The leaves are
+    /// hashed in parallel, keeping their order.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of byte data from which to construct the tree.
+    /// * `num_threads` - The number of threads to be used for parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the levels of the constructed tree, from the leaves up to the
+    /// root, or a `MerkleError` if the thread pool cannot be created.
+    fn build_tree(data: &[&[u8]], num_threads: usize) -> Result<Vec<Vec<[u8; 32]>>, MerkleError> {
+        Self::in_thread_pool(num_threads, || {
+            let leaf_hashes: Vec<[u8; 32]> = data.par_iter().map(|d| H::hash_leaf(d)).collect();
+            Self::levels_from_leaf_hashes(leaf_hashes)
+        })
+    }
+
+    /// Constructs the Merkle tree on top of already computed leaf hashes.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_hashes` - The hashes of the leaves, in order.
+    /// * `num_threads` - The number of threads to be used for parallel construction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the levels of the constructed tree, from the leaves up to the
+    /// root, or a `MerkleError` if the thread pool cannot be created.
+    fn build_tree_from_hashes(
+        leaf_hashes: Vec<[u8; 32]>,
+        num_threads: usize,
+    ) -> Result<Vec<Vec<[u8; 32]>>, MerkleError> {
+        Self::in_thread_pool(num_threads, || Self::levels_from_leaf_hashes(leaf_hashes))
+    }
+
+    /// Computes the internal levels on top of the leaf hashes, on the current rayon pool.
+    fn levels_from_leaf_hashes(leaf_hashes: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+        Self::levels_from_leaf_hashes_with(leaf_hashes, PairingPolicy::Promote)
+    }
+
+    /// Computes the internal levels like `levels_from_leaf_hashes`, combining the last node
+    /// of odd-sized levels according to `pairing`.
+    fn levels_from_leaf_hashes_with(
+        leaf_hashes: Vec<[u8; 32]>,
+        pairing: PairingPolicy,
+    ) -> Vec<Vec<[u8; 32]>> {
+        let data_length = leaf_hashes.len();
+        let mut levels = vec![leaf_hashes];
+        Self::rehash_levels(&mut levels, data_length, 0, 0, pairing);
+        levels
+    }
+
+    /// Recomputes the internal nodes covering any leaf from `first_changed` onwards.
+    ///
+    /// The nodes left of them are kept, so only the right edge of the tree is rebuilt when
+    /// leaves are appended. Levels are added as the tree grows. Each level is combined in
+    /// parallel, pairing neighbouring nodes and combining the last node of an odd-sized level
+    /// according to `pairing`.
+    ///
+    /// # Arguments
+    ///
+    /// * `levels` - The levels of the tree, holding all leaves of the tree at level 0.
+    /// * `data_length` - The number of leaves in the tree.
+    /// * `pruned_length` - The number of leading leaves whose subtrees are pruned.
+    /// * `first_changed` - The index of the first leaf that changed or was added.
+    /// * `pairing` - How the last node of an odd-sized level is combined.
+    fn rehash_levels(
+        levels: &mut Vec<Vec<[u8; 32]>>,
+        data_length: usize,
+        pruned_length: usize,
+        first_changed: usize,
+        pairing: PairingPolicy,
+    ) {
+        let height = tree_height(data_length);
+        levels.resize_with(height + 1, Vec::new);
+
+        for level in 0..height {
+            let first_parent = first_changed >> (level + 1);
+            let (lower, upper) = levels.split_at_mut(level + 1);
+            let children = &lower[level][2 * first_parent - level_offset(pruned_length, level)..];
+
+            let parents = &mut upper[0];
+            parents.truncate(first_parent - level_offset(pruned_length, level + 1));
+            parents.par_extend(
+                children
+                    .par_chunks(2)
+                    .map(|pair| Self::parent_hash(pair, pairing)),
+            );
+        }
+    }
+
+    /// Computes the parent of a pair of sibling nodes, or combines a node without a sibling
+    /// according to `pairing`.
+    fn parent_hash(children: &[[u8; 32]], pairing: PairingPolicy) -> [u8; 32] {
+        match children {
+            [left, right] => H::hash_node(left, right),
+            [node] => match pairing.lone_sibling(node) {
+                Some(sibling) => H::hash_node(node, &sibling),
+                None => *node,
+            },
+            _ => unreachable!("Nodes are combined in pairs"),
+        }
+    }
+
+    /// Constructs the Merkle tree like `build_tree`, reusing the hashes of repeated content.
+    ///
+    /// Internal method used during the creation of a deduplicating Prover instance. The
+    /// distinct leaf values, and the distinct pairs of each level not seen before, are hashed
+    /// in parallel on the current rayon pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of byte data from which to construct the tree.
+    ///
+    /// # Returns
+    ///
+    /// The levels of the constructed tree and the number of hashes actually computed.
+    fn build_tree_deduped(data: &[&[u8]]) -> (Vec<Vec<[u8; 32]>>, usize) {
+        // Hash each distinct leaf value once
+        let distinct_leaves: Vec<&[u8]> = data
+            .iter()
+            .copied()
+            .collect::<HashSet<&[u8]>>()
+            .into_iter()
+            .collect();
+        let mut hash_count = distinct_leaves.len();
+        let leaf_cache: HashMap<&[u8], [u8; 32]> = distinct_leaves
+            .par_iter()
+            .map(|d| (*d, H::hash_leaf(d)))
+            .collect();
+        let leaf_hashes = data.iter().map(|d| leaf_cache[d]).collect();
+        let mut levels: Vec<Vec<[u8; 32]>> = vec![leaf_hashes];
+
+        // Hash each distinct pair of children once
+        let mut node_cache: HashMap<([u8; 32], [u8; 32]), [u8; 32]> = HashMap::new();
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let new_pairs: Vec<([u8; 32], [u8; 32])> = level
+                .chunks_exact(2)
+                .map(|children| (children[0], children[1]))
+                .filter(|pair| !node_cache.contains_key(pair))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            hash_count += new_pairs.len();
+            node_cache.par_extend(
+                new_pairs
+                    .into_par_iter()
+                    .map(|(left, right)| ((left, right), H::hash_node(&left, &right))),
+            );
+
+            let next_level = level
+                .chunks(2)
+                .map(|children| match children {
+                    [left, right] => node_cache[&(*left, *right)],
+                    // Promote the last node of an uneven level, as in build_tree
+                    _ => children[0],
+                })
+                .collect();
+            levels.push(next_level);
+        }
+
+        (levels, hash_count)
+    }
+}
+
+/// Configures and builds a `MerkleProver`, see `Prover::builder`.
+pub struct ProverBuilder<'a, H: Hasher> {
+    data: &'a [&'a str],
+    num_threads: usize,
+    salt: Option<&'a [u8]>,
+    retain_data: bool,
+    max_leaves: usize,
+    pairing: PairingPolicy,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl<'a, H: Hasher> ProverBuilder<'a, H> {
+    /// Sets the data to build the tree from.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A slice of string data to construct the Merkle tree.
+    pub fn data(mut self, data: &'a [&'a str]) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the number of threads to be used in the parallel construction.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_threads` - The number of threads, which must not be zero.
+    pub fn threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Sets a salt that is hashed into every leaf, so the leaf hashes of a small set of
+    /// possible values cannot be looked up in a precomputed table.
+    ///
+    /// The salt is not part of the proofs; verifiers check them with
+    /// `Verifier::verify_proof_for_salted_data` and the same salt. Leaves appended or updated
+    /// later are salted too.
+    ///
+    /// # Arguments
+    ///
+    /// * `salt` - The salt, prepended to the data of each leaf before hashing.
+    pub fn salt(mut self, salt: &'a [u8]) -> Self {
+        self.salt = Some(salt);
+        self
+    }
+
+    /// Sets whether the Prover keeps a copy of the leaf data, see `new_retaining`.
+    ///
+    /// The data is not retained by default, which saves memory for callers that only prove
+    /// leaves by index.
+    ///
+    /// # Arguments
+    ///
+    /// * `retain_data` - Whether the leaf data is retained.
+    pub fn retain_data(mut self, retain_data: bool) -> Self {
+        self.retain_data = retain_data;
+        self
+    }
+
+    /// Sets the maximum number of leaves, which defaults to `MAX_DATA_SIZE`.
+    ///
+    /// The limit is inclusive and kept by the built Prover, so appending beyond it fails as
+    /// well. It is part of the serialized state and of checkpoints.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_leaves` - The maximum number of leaves in the tree.
+    pub fn max_leaves(mut self, max_leaves: usize) -> Self {
+        self.max_leaves = max_leaves;
+        self
+    }
+
+    /// Selects the minimal unbalanced layout, where the last node of an odd-sized level is
+    /// promoted instead of padded, so the rightmost subtree is shorter and its leaves have
+    /// shorter authentication paths.
+    ///
+    /// This is the default layout, so the call only makes the choice explicit. It is the same
+    /// as `pairing(PairingPolicy::Promote)`.
+    pub fn unbalanced(self) -> Self {
+        self.pairing(PairingPolicy::Promote)
+    }
+
+    /// Sets how the last node of an odd-sized level is combined, for compatibility with
+    /// systems that duplicate it or pair it with a constant. It defaults to
+    /// `PairingPolicy::Promote`.
+    ///
+    /// Proofs of trees that do not promote must be verified with a Verifier created by
+    /// `MerkleVerifier::with_pairing` and the same policy. Multiproofs, range proofs,
+    /// consistency proofs, compact ranges and checkpoints rely on promotion and fail with
+    /// `MerkleError::PairingNotSupported` for other policies.
+    ///
+    /// # Arguments
+    ///
+    /// * `pairing` - The pairing policy of the tree.
+    pub fn pairing(mut self, pairing: PairingPolicy) -> Self {
+        self.pairing = pairing;
+        self
+    }
+
+    /// Builds the Merkle tree from the configured data.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Prover instance, or a `MerkleError` if any issues arise.
+    pub fn build(self) -> Result<MerkleProver<H>, MerkleError> {
+        MerkleProver::<H>::validate_with_max(self.data.len(), self.num_threads, self.max_leaves)?;
+        let levels = MerkleProver::<H>::in_thread_pool(self.num_threads, || {
+            let leaf_hashes: Vec<[u8; 32]> = self
+                .data
+                .par_iter()
+                .map(|d| match self.salt {
+                    Some(salt) => H::hash_salted_leaf(salt, d.as_bytes()),
+                    None => H::hash_leaf(d.as_bytes()),
+                })
+                .collect();
+            MerkleProver::<H>::levels_from_leaf_hashes_with(leaf_hashes, self.pairing)
+        })?;
+
+        let mut prover = MerkleProver::from_levels(levels);
+        prover.salt = self.salt.map(<[u8]>::to_vec);
+        prover.max_leaves = self.max_leaves;
+        prover.pairing = self.pairing;
+        if self.retain_data {
+            prover.data = Some(self.data.iter().map(|d| d.as_bytes().to_vec()).collect());
+        }
+        Ok(prover)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{hash_data_sequences, hash_leaf, hash_node, EMPTY_HASH};
+    use crate::Verifier;
+    use std::fs;
+
+    #[test]
+    fn test_construct_and_get_root_hash() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let num_threads = 1;
+        let root_hash;
+
+        // Construct the initial prover and get its root hash
+        match Prover::new(&data, num_threads) {
+            Ok(prover) => {
+                match prover.get_root_hash() {
+                    Ok(hash) => {
+                        assert!(!hash.is_empty(), "Root hash should not be empty");
+                        root_hash = hash; // Assigning the hash to root_hash for use later
+                    }
+                    Err(e) => {
+                        panic!("Failed to get root hash: {}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                panic!("Failed to create Prover instance: {}", e);
+            }
+        }
+
+        // Construct a second prover and compare its root hash to the first
+        match Prover::new(&data, num_threads) {
+            Ok(prover2) => match prover2.get_root_hash() {
+                Ok(hash2) => {
+                    assert_eq!(
+                        root_hash, hash2,
+                        "Root hashes should match for the same data"
+                    );
+                }
+                Err(e) => {
+                    panic!("Failed to get root hash from prover2: {}", e);
+                }
+            },
+            Err(e) => {
+                panic!("Failed to create Prover2 instance: {}", e);
+            }
+        }
+
+        // Construct a prover with modified data and ensure its root hash is different
+        let mut modified_data = data.clone();
+        modified_data[0] = "modified_data";
+
+        match Prover::new(&modified_data, num_threads) {
+            Ok(prover_modified) => match prover_modified.get_root_hash() {
+                Ok(modified_hash) => {
+                    assert_ne!(
+                        root_hash, modified_hash,
+                        "Root hash should change when data is modified"
+                    );
+                }
+                Err(e) => {
+                    panic!("Failed to get root hash from modified prover: {}", e);
+                }
+            },
+            Err(e) => {
+                panic!("Failed to create Prover with modified data instance: {}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_construct_and_get_root_hash_parallel() {
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+
+        let mut reference_root_hash: Option<[u8; 32]> = None;
+
+        // Testing num_threads from 1 to 8
+        for num_threads in 1..=8 {
+            match Prover::new(&data, num_threads) {
+                Ok(prover) => match prover.get_root_hash() {
+                    Ok(hash) => {
+                        assert!(
+                            !hash.is_empty(),
+                            "Root hash should not be empty with {} threads",
+                            num_threads
+                        );
+
+                        if let Some(ref root) = reference_root_hash {
+                            assert_eq!(
+                                &hash, root,
+                                "Root hash mismatch at {} threads!",
+                                num_threads
+                            );
+                        } else {
+                            reference_root_hash = Some(hash);
+                        }
+                    }
+                    Err(e) => {
+                        panic!(
+                            "Failed to get root hash with {} threads: {}",
+                            num_threads, e
+                        );
+                    }
+                },
+                Err(e) => {
+                    panic!(
+                        "Failed to create Prover instance with {} threads: {}",
+                        num_threads, e
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_proof_complete_tree() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let num_threads = 1;
+        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+
+            assert_eq!(proof.leaf_index, leaf_index);
+
+            assert_eq!(proof.leaf_hash, hash_leaf(leaf.as_bytes()));
+
+            // The height of the tree should be ceil(log2(4)) = 2
+            assert_eq!(
+                proof.authentication_path.len(),
+                2,
+                "Failed at leaf_index: {}",
+                leaf_index
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_proof_non_complete_tree() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let num_threads = 1;
+        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+
+            assert_eq!(proof.leaf_index, leaf_index);
+
+            assert_eq!(proof.leaf_hash, hash_leaf(leaf.as_bytes()));
+
+            // The height of the tree should be ceil(log2(5)) = 3, except for the last leaf,
+            // which is promoted past the two lower levels
+            assert_eq!(
+                proof.authentication_path.len(),
+                if leaf_index == 4 { 1 } else { 3 },
+                "Failed at leaf_index: {}",
+                leaf_index
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder_unbalanced() {
+        for (data_length, path_lengths) in [(5, vec![3, 3, 3, 3, 1]), (6, vec![3, 3, 3, 3, 2, 2])] {
+            let data: Vec<String> = (0..data_length).map(|i| format!("data{}", i)).collect();
+            let data: Vec<&str> = data.iter().map(String::as_str).collect();
+            let prover = Prover::builder()
+                .data(&data)
+                .unbalanced()
+                .build()
+                .expect("Failed to create prover");
+            let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+            assert_eq!(
+                prover.get_root_hash().unwrap(),
+                Prover::new(&data, 1).unwrap().get_root_hash().unwrap()
+            );
+            for (leaf_index, &path_length) in path_lengths.iter().enumerate() {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                assert_eq!(proof.authentication_path.len(), path_length);
+                assert!(verifier.verify_proof(&proof));
+            }
+        }
+
+        assert_eq!(
+            Prover::builder().unbalanced().build().err(),
+            Some(MerkleError::EmptyData)
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let data: Vec<String> = (0..100).map(|i| format!("data{}", i)).collect();
+        let data: Vec<&str> = data.iter().map(String::as_str).collect();
+        let expected_root = Prover::new(&data, 1).unwrap().get_root_hash().unwrap();
+
+        for num_threads in [2, 4, 7] {
+            let prover = Prover::builder()
+                .threads(num_threads)
+                .data(&data)
+                .build()
+                .expect("Failed to create prover");
+            assert_eq!(prover.get_root_hash().unwrap(), expected_root);
+            assert_eq!(
+                prover.get_proof(42).unwrap().authentication_path,
+                Prover::new(&data, num_threads)
+                    .unwrap()
+                    .get_proof(42)
+                    .unwrap()
+                    .authentication_path
+            );
+        }
+
+        // The defaults build with a single thread
+        let prover = Prover::builder().data(&data).build().unwrap();
+        assert_eq!(prover.get_root_hash().unwrap(), expected_root);
+
+        assert_eq!(
+            Prover::builder().data(&data).threads(0).build().err(),
+            Some(MerkleError::ZeroThreads)
+        );
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let first: [&[&[u8]]; 2] = [&[b"ab", b"c"], &[b"data1"]];
+        let second: [&[&[u8]]; 2] = [&[b"a", b"bc"], &[b"data1"]];
+        let prover = Prover::from_parts(&first, 2).unwrap();
+        let other = Prover::from_parts(&second, 1).unwrap();
+
+        // The splitting of a leaf changes its hash and the root
+        assert_ne!(prover.root(), other.root());
+        assert_eq!(prover.diff(&other), Ok(vec![0]));
+
+        let verifier = Verifier::new(prover.root());
+        for (leaf_index, parts) in first.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_hash, Sha256Hasher::hash_leaf_parts(parts));
+            assert!(verifier.verify_proof(&proof));
+        }
+        assert_eq!(
+            Prover::from_parts(&[], 1).err(),
+            Some(MerkleError::EmptyData)
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_new_async() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+        let prover = Prover::new_async(&data, 2).await.unwrap();
+        assert_eq!(prover.root(), Prover::new(&data, 1).unwrap().root());
+
+        let proof = prover.get_proof(3).unwrap();
+        assert!(Verifier::new(prover.root()).verify_proof_for_data("data3", &proof));
+        assert_eq!(
+            Prover::new_async(&[], 1).await.err(),
+            Some(MerkleError::EmptyData)
+        );
+    }
+
+    #[test]
+    fn test_pairing_policies() {
+        let policies = [
+            PairingPolicy::Duplicate,
+            PairingPolicy::Promote,
+            PairingPolicy::HashWithZero,
+        ];
+        let (a, b, c) = (
+            hash_leaf(b"data0"),
+            hash_leaf(b"data1"),
+            hash_leaf(b"data2"),
+        );
+        let expected_roots = [
+            hash_node(&hash_node(&a, &b), &hash_node(&c, &c)),
+            hash_node(&hash_node(&a, &b), &c),
+            hash_node(&hash_node(&a, &b), &hash_node(&c, &[0u8; 32])),
+        ];
+
+        let data = ["data0", "data1", "data2", "data3", "data4"];
+        for size in [3, 5] {
+            let provers: Vec<Prover> = policies
+                .iter()
+                .map(|&pairing| {
+                    Prover::builder()
+                        .data(&data[..size])
+                        .pairing(pairing)
+                        .build()
+                        .unwrap()
+                })
+                .collect();
+            if size == 3 {
+                let roots: Vec<_> = provers.iter().map(Prover::root).collect();
+                assert_eq!(roots, expected_roots);
+            }
+
+            for (i, prover) in provers.iter().enumerate() {
+                // Every policy gives a distinct root
+                for other in &provers[i + 1..] {
+                    assert_ne!(prover.root(), other.root());
+                }
+
+                for leaf_index in 0..size {
+                    let proof = prover.get_proof(leaf_index).unwrap();
+                    assert!(Verifier::with_pairing(prover.root(), policies[i]).verify_proof(&proof));
+                }
+
+                // The proof of the last leaf involves the lone node, and verifies only under
+                // the matching policy
+                let proof = prover.get_proof(size - 1).unwrap();
+                for (j, &pairing) in policies.iter().enumerate() {
+                    let verifier = Verifier::with_pairing(prover.root(), pairing);
+                    assert_eq!(verifier.verify_proof(&proof), i == j);
+                }
+            }
+
+            // Stand-in siblings are part of the path, so every path has the full height
+            let proof = provers[0].get_proof(size - 1).unwrap();
+            assert_eq!(proof.authentication_path.len(), tree_height(size));
+            assert_eq!(
+                proof.authentication_path[tree_height(size) - 1],
+                proof.leaf_hash
+            );
+            let proof = provers[2].get_proof(size - 1).unwrap();
+            assert_eq!(proof.authentication_path[tree_height(size) - 1], [0u8; 32]);
+        }
+    }
+
+    #[test]
+    fn test_pairing_policy_updates() {
+        let data = vec![
+            "data0", "data1", "data2", "data3", "data4", "data5", "data6",
+        ];
+        let build = |leaves: &[&str], pairing| {
+            Prover::builder()
+                .data(leaves)
+                .pairing(pairing)
+                .build()
+                .unwrap()
+        };
+
+        for pairing in [PairingPolicy::Duplicate, PairingPolicy::HashWithZero] {
+            // Appending, updating and restoring keep the policy
+            let mut prover = build(&data[..3], pairing);
+            prover.append(&data[3..]).unwrap();
+            assert_eq!(prover.root(), build(&data, pairing).root());
+
+            let mut changed = data.clone();
+            changed[6] = "changed";
+            assert_eq!(
+                prover.root_if_changed(6, "changed").unwrap(),
+                build(&changed, pairing).root()
+            );
+            assert_eq!(
+                prover.update_leaf(6, "changed").unwrap(),
+                build(&changed, pairing).root()
+            );
+
+            let mut restored = Prover::deserialize(&prover.serialize()).unwrap();
+            restored.append(&["data7", "data8"]).unwrap();
+            prover.append(&["data7", "data8"]).unwrap();
+            assert_eq!(restored.root(), prover.root());
+
+            // Operations that rely on promotion are refused
+            let not_supported = Some(MerkleError::PairingNotSupported);
+            assert_eq!(prover.get_multiproof(&[0, 1]).err(), not_supported);
+            assert_eq!(prover.range_proof(0, 2).err(), not_supported);
+            assert_eq!(prover.consistency_proof(3).err(), not_supported);
+            assert_eq!(prover.compact_range(3).err(), not_supported);
+        }
+
+        // Promoting is the default
+        assert_eq!(
+            build(&data, PairingPolicy::Promote).root(),
+            Prover::new(&data, 1).unwrap().root()
+        );
+        assert_eq!(PairingPolicy::default(), PairingPolicy::Promote);
+    }
+
+    #[test]
+    fn test_subtree() {
+        let data = [
+            "data0", "data1", "data2", "data3", "data4", "data5", "data6", "data7",
+        ];
+        let prover = Prover::new_retaining(&data, 1).expect("Failed to create prover");
+
+        // The left and right halves of an 8-leaf tree
+        for (position, leaves) in [(0, &data[..4]), (1, &data[4..])] {
+            let subtree = prover.subtree(2, position).unwrap();
+            let subtree_root = prover.node_hash(2, position).unwrap();
+            assert_eq!(subtree.root(), subtree_root);
+            assert_eq!(subtree.root(), Prover::new(leaves, 1).unwrap().root());
+            assert_eq!(subtree.leaf_count(), 4);
+
+            let verifier = Verifier::new(subtree_root);
+            for (leaf_index, leaf) in leaves.iter().enumerate() {
+                let proof = subtree.get_proof(leaf_index).unwrap();
+                assert!(verifier.verify_proof_for_data(leaf, &proof));
+                assert_eq!(subtree.leaf_data(leaf_index), Some(*leaf));
+            }
+        }
+
+        // The whole tree, a single leaf, and nodes that do not exist
+        assert_eq!(prover.subtree(3, 0).unwrap().root(), prover.root());
+        assert_eq!(prover.subtree(0, 5).unwrap().root(), hash_leaf(b"data5"));
+        assert!(prover.subtree(2, 2).is_none());
+        assert!(prover.subtree(4, 0).is_none());
+
+        // A partial subtree on the right edge of a promoting tree has the hash of its node
+        let prover = Prover::new(&data[..6], 1).unwrap();
+        let subtree = prover.subtree(2, 1).unwrap();
+        assert_eq!(subtree.leaf_count(), 2);
+        assert_eq!(Some(subtree.root()), prover.node_hash(2, 1));
+        let duplicating = Prover::builder()
+            .data(&data[..6])
+            .pairing(PairingPolicy::Duplicate)
+            .build()
+            .unwrap();
+        assert!(duplicating.subtree(2, 1).is_none());
+        assert_eq!(
+            duplicating.subtree(2, 0).unwrap().root(),
+            duplicating.node_hash(2, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let data: Vec<String> = (0..11).map(|i| format!("data{}", i)).collect();
+        let leaves: Vec<&str> = data.iter().map(String::as_str).collect();
+        let prover = Prover::new(&leaves, 1).unwrap();
+
+        // No changes
+        let same = Prover::new(&leaves, 2).unwrap();
+        assert_eq!(prover.diff(&same), Ok(vec![]));
+
+        // One changed leaf, including the promoted last one
+        for changed_index in [0, 5, 10] {
+            let mut changed = leaves.clone();
+            changed[changed_index] = "changed";
+            let other = Prover::new(&changed, 1).unwrap();
+            assert_eq!(prover.diff(&other), Ok(vec![changed_index]));
+            assert_eq!(other.diff(&prover), Ok(vec![changed_index]));
+        }
+
+        // Several changed leaves come out sorted
+        let mut changed = leaves.clone();
+        for changed_index in [9, 1, 2, 7] {
+            changed[changed_index] = "changed";
+        }
+        let other = Prover::new(&changed, 1).unwrap();
+        assert_eq!(prover.diff(&other), Ok(vec![1, 2, 7, 9]));
+
+        // A single leaf tree
+        let one = Prover::new(&["data0"], 1).unwrap();
+        assert_eq!(one.diff(&Prover::new(&["data1"], 1).unwrap()), Ok(vec![0]));
+    }
+
+    #[test]
+    fn test_builder_max_leaves() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+
+        // The custom limit is inclusive, and reported when exceeded
+        let mut prover = Prover::builder()
+            .data(&data[..4])
+            .max_leaves(4)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Prover::builder().data(&data).max_leaves(4).build().err(),
+            Some(MerkleError::DataTooLarge { len: 5, max: 4 })
+        );
+
+        // The Prover keeps its limit when appending
+        assert_eq!(
+            prover.append(&["data4"]),
+            Err(MerkleError::DataTooLarge { len: 5, max: 4 })
+        );
+        assert_eq!(prover.leaf_count(), 4);
+
+        // The salted construction enforces the limit too
+        assert!(Prover::builder()
+            .data(&data)
+            .salt(b"salt")
+            .max_leaves(4)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_max_leaves_above_default() {
+        let large_data: Vec<String> = (0..=MAX_DATA_SIZE).map(|i| i.to_string()).collect();
+        let data_refs: Vec<&str> = large_data.iter().map(AsRef::as_ref).collect();
+
+        assert!(Prover::new(&data_refs, 1).is_err());
+        let mut prover = Prover::builder()
+            .data(&data_refs)
+            .max_leaves(MAX_DATA_SIZE + 2)
+            .build()
+            .expect("A larger custom limit should accept more than MAX_DATA_SIZE leaves");
+        assert_eq!(prover.leaf_count(), MAX_DATA_SIZE + 1);
+        let proof = prover.get_proof(MAX_DATA_SIZE).unwrap();
+        assert!(Verifier::new(prover.root()).verify_proof(&proof));
+
+        prover.append(&["one more"]).unwrap();
+        assert_eq!(
+            prover.append(&["one too many"]),
+            Err(MerkleError::DataTooLarge {
+                len: MAX_DATA_SIZE + 3,
+                max: MAX_DATA_SIZE + 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder_salt() {
+        let data = vec!["yes", "no", "yes", "no", "no"];
+        let salted = |data: &[&str], salt: &[u8]| {
+            Prover::builder()
+                .data(data)
+                .salt(salt)
+                .threads(2)
+                .build()
+                .expect("Failed to create prover")
+        };
+
+        let mut prover = salted(&data[..3], b"salt");
+        assert_eq!(
+            prover.node_hash(0, 1),
+            Some(hash_data_sequences(&[&[0], b"salt", b"no"]))
+        );
+        assert_ne!(prover.root(), Prover::new(&data[..3], 1).unwrap().root());
+        assert_ne!(prover.root(), salted(&data[..3], b"pepper").root());
+
+        // Appended and updated leaves are salted like the initial ones
+        prover.append(&data[3..]).unwrap();
+        assert_eq!(prover.root(), salted(&data, b"salt").root());
+        let root = prover.update_leaf(0, "no").unwrap();
+        assert_eq!(
+            root,
+            salted(&["no", "no", "yes", "no", "no"], b"salt").root()
+        );
+        assert_eq!(prover.index_of("yes"), Some(2));
+
+        // The salt survives serialization
+        let mut restored = Prover::deserialize(&prover.serialize()).unwrap();
+        restored.append(&["yes"]).unwrap();
+        prover.append(&["yes"]).unwrap();
+        assert_eq!(restored.root(), prover.root());
+    }
+
+    #[test]
+    fn test_leaf_data() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+
+        let prover = Prover::builder()
+            .data(&data)
+            .retain_data(true)
+            .build()
+            .expect("Failed to create prover");
+        for (index, leaf) in data.iter().enumerate() {
+            assert_eq!(prover.leaf_data(index), Some(*leaf));
+        }
+        assert_eq!(prover.leaf_data(data.len()), None);
+        assert_eq!(
+            prover.root(),
+            Prover::new(&data, 1).unwrap().root(),
+            "Retention must not change the tree"
+        );
+
+        // Without the flag nothing is retained
+        for prover in [
+            Prover::builder().data(&data).build().unwrap(),
+            Prover::builder()
+                .data(&data)
+                .retain_data(false)
+                .build()
+                .unwrap(),
+            Prover::new(&data, 1).unwrap(),
+        ] {
+            assert!((0..data.len()).all(|index| prover.leaf_data(index).is_none()));
+            assert_eq!(
+                prover.get_proof_inline(0).err(),
+                Some(MerkleError::DataNotRetained)
+            );
+        }
+
+        // Retained data follows appends and survives serialization
+        let mut prover = Prover::new_retaining(&data, 1).unwrap();
+        prover.append(&["data5"]).unwrap();
+        let restored = Prover::deserialize(&prover.serialize()).unwrap();
+        assert_eq!(restored.leaf_data(5), Some("data5"));
+
+        // Data that is not valid UTF-8 is not returned as a string
+        let mut prover = Prover::from_bytes(&[&[0xff, 0xfe]], 1).unwrap();
+        prover.data = Some(vec![vec![0xff, 0xfe]]);
+        assert_eq!(prover.leaf_data(0), None);
+    }
+
+    #[test]
+    fn test_node_hash() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let height = tree_height(data.len());
+
+        assert_eq!(prover.node_hash(0, 4), Some(hash_leaf(b"data4")));
+        assert_eq!(prover.node_hash(height, 0), Some(prover.root()));
+
+        // Walk from leaf 3 up to the root, matching the siblings of its proof
+        let leaf_index = 3;
+        let mut path = prover.get_proof(leaf_index).unwrap().authentication_path;
+        path.reverse();
+        let mut node = prover.node_hash(0, leaf_index).unwrap();
+        for (level, sibling) in path.iter().enumerate() {
+            let position = leaf_index >> level;
+            assert_eq!(prover.node_hash(level, position), Some(node));
+            assert_eq!(prover.node_hash(level, position ^ 1), Some(*sibling));
+            node = if position % 2 == 1 {
+                hash_node(sibling, &node)
+            } else {
+                hash_node(&node, sibling)
+            };
+        }
+        assert_eq!(prover.node_hash(height, 0), Some(node));
+
+        // Out-of-range coordinates
+        assert_eq!(prover.node_hash(0, 6), None);
+        assert_eq!(prover.node_hash(1, 3), None);
+        assert_eq!(prover.node_hash(height, 1), None);
+        assert_eq!(prover.node_hash(height + 1, 0), None);
+    }
+
+    #[test]
+    fn test_get_proof_single_leaf() {
+        let prover = Prover::new(&["data1"], 1).expect("Failed to create prover");
+        assert_eq!(prover.get_root_hash().unwrap(), hash_leaf(b"data1"));
+        assert_eq!(prover.height(), 0);
+
+        let proof = prover.get_proof(0).unwrap();
+        assert_eq!(proof.leaf_index, 0);
+        assert_eq!(proof.tree_size, 1);
+        assert_eq!(proof.leaf_hash, hash_leaf(b"data1"));
+        assert!(proof.authentication_path.is_empty());
+        assert!(prover.get_proof(1).is_err());
+
+        // Appending to a single leaf gives the leaf a sibling
+        let mut prover = prover;
+        prover.append(&["data2"]).unwrap();
+        assert_eq!(
+            prover.get_proof(0).unwrap().authentication_path,
+            vec![hash_leaf(b"data2")]
+        );
+    }
+
+    #[test]
+    fn test_get_proof_out_of_bounds() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let num_threads = 1;
+        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+
+        // Attempt to get a proof for an out-of-bounds leaf_index
+        let result = prover.get_proof(data.len());
+
+        // Validate the result is an error
+        assert!(
+            result.is_err(),
+            "Expected an error for out-of-bounds leaf_index"
+        );
+    }
+
+    #[test]
+    fn test_get_proofs() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        let leaf_indices = [4, 0, 2, 2, 1];
+        let proofs = prover.get_proofs(&leaf_indices).unwrap();
+        assert_eq!(proofs.len(), leaf_indices.len());
+
+        for (proof, &leaf_index) in proofs.iter().zip(leaf_indices.iter()) {
+            let expected = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_index, expected.leaf_index);
+            assert_eq!(proof.leaf_hash, expected.leaf_hash);
+            assert_eq!(proof.authentication_path, expected.authentication_path);
+        }
+
+        // Proofs for the same leaf are equal, proofs for different leaves are not
+        assert_eq!(proofs[2], proofs[3]);
+        assert_ne!(proofs[0], proofs[1]);
+        assert_ne!(proofs[1], proofs[2]);
+
+        assert!(prover.get_proofs(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_proofs_parallel_preserves_order() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        // Enough indices, in a scrambled order with repeats, to take the parallel path
+        let leaf_indices: Vec<usize> = (0..3 * PARALLEL_PROOFS_THRESHOLD)
+            .map(|i| (i * 7919) % data.len())
+            .collect();
+        let proofs = prover.get_proofs(&leaf_indices).unwrap();
+        assert_eq!(proofs.len(), leaf_indices.len());
+
+        for (proof, &leaf_index) in proofs.iter().zip(leaf_indices.iter()) {
+            let expected = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_index, leaf_index);
+            assert_eq!(proof.to_bytes(), expected.to_bytes());
+        }
+
+        let mut leaf_indices = leaf_indices;
+        leaf_indices.push(data.len());
+        assert_eq!(
+            prover.get_proofs(&leaf_indices).err(),
+            Some(MerkleError::IndexOutOfBounds {
+                index: data.len(),
+                len: data.len()
+            })
+        );
+    }
+
+    #[test]
+    fn test_proofs() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().take(37).collect();
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.root());
+
+        let proofs: Vec<MerkleProof> = prover.proofs().collect();
+        assert_eq!(proofs.len(), data.len());
+        for (leaf_index, proof) in proofs.iter().enumerate() {
+            assert_eq!(proof.leaf_index, leaf_index);
+            assert!(verifier.verify_proof_for_data(data[leaf_index], proof));
+        }
+
+        // The proofs are generated lazily, one per call to next
+        let mut proofs = prover.proofs();
+        assert_eq!(proofs.next().unwrap().leaf_index, 0);
+        assert_eq!(proofs.nth(10).unwrap().leaf_index, 11);
+
+        // Only the leaves right of the frontier are proven after resuming
+        let mut resumed = Prover::from_frontier(&prover.frontier(), data.len()).unwrap();
+        resumed.append(&["appended0", "appended1"]).unwrap();
+        let indices: Vec<usize> = resumed.proofs().map(|proof| proof.leaf_index).collect();
+        assert_eq!(indices, vec![36, 37, 38]);
+    }
+
+    #[test]
+    fn test_get_proofs_out_of_bounds() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        // One invalid index fails the whole batch
+        assert!(prover.get_proofs(&[0, 1, data.len(), 2]).is_err());
+    }
+
+    #[test]
+    fn test_get_proof_inline() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let num_threads = 1;
+
+        // Provers not created in retaining mode cannot embed data
+        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+        assert!(prover.get_proof_inline(0).is_err());
+        assert!(prover.get_proof(0).unwrap().leaf_data.is_none());
+
+        let prover = Prover::new_retaining(&data, num_threads).expect("Failed to create prover");
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof_inline(leaf_index).unwrap();
+            assert_eq!(proof.leaf_data.as_deref(), Some(leaf.as_bytes()));
+        }
+        assert!(prover.get_proof_inline(data.len()).is_err());
+    }
+
+    #[test]
+    fn test_get_proof_with_data() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        assert_eq!(
+            prover.get_proof_with_data(0).err(),
+            Some(MerkleError::DataNotRetained)
+        );
+
+        let prover = Prover::new_retaining(&data, 1).expect("Failed to create prover");
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let (proof, leaf_data) = prover.get_proof_with_data(leaf_index).unwrap();
+            assert_eq!(leaf_data, *leaf);
+            assert_eq!(hash_leaf(leaf_data.as_bytes()), proof.leaf_hash);
+        }
+        assert!(prover.get_proof_with_data(data.len()).is_err());
+    }
+
+    #[test]
+    fn test_generate_proof_found() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.generate_proof(leaf).unwrap();
+            assert_eq!(proof.leaf_index, leaf_index);
+            assert_eq!(proof.leaf_hash, hash_leaf(leaf.as_bytes()));
+            assert_eq!(
+                proof.authentication_path,
+                prover.get_proof(leaf_index).unwrap().authentication_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_string_leaves() {
+        let data = vec!["", "data1", "", "", "data4"];
+        let prover = Prover::new(&data, 2).expect("Empty strings should be valid leaves");
+        let verifier = Verifier::new(prover.root());
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_hash, hash_leaf(leaf.as_bytes()));
+            assert!(verifier.verify_proof_for_data(leaf, &proof));
+        }
+
+        // An empty leaf is hashed with its prefix, so it differs from no data at all
+        assert_ne!(hash_leaf(b""), EMPTY_HASH);
+        assert_ne!(Prover::new(&[""], 1).unwrap().root(), EMPTY_HASH);
+        assert_eq!(prover.generate_proof("").unwrap().leaf_index, 0);
+    }
+
+    #[test]
+    fn test_generate_proof_not_found() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        assert!(prover.generate_proof("data6").is_err());
+    }
+
+    #[test]
+    fn test_generate_proof_duplicate_value() {
+        let data = vec!["data1", "data2", "duplicate", "data4", "duplicate"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        // The first occurrence is proven
+        let proof = prover.generate_proof("duplicate").unwrap();
+        assert_eq!(proof.leaf_index, 2);
+    }
+
+    #[test]
+    fn test_index_of() {
+        let data = vec!["data1", "data2", "duplicate", "data4", "duplicate"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        assert_eq!(prover.index_of("data1"), Some(0));
+        assert_eq!(prover.index_of("data4"), Some(3));
+        assert_eq!(prover.index_of("duplicate"), Some(2));
+        assert_eq!(prover.index_of("data6"), None);
+
+        for leaf in ["data1", "data2", "duplicate", "data4"] {
+            let leaf_index = prover.index_of(leaf).unwrap();
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_hash, hash_leaf(leaf.as_bytes()));
+            assert!(verifier.verify_proof(&proof));
+        }
+
+        // The lookup follows changes to the leaves
+        let mut prover = prover;
+        prover.append(&["data6"]).unwrap();
+        assert_eq!(prover.index_of("data6"), Some(5));
+        prover.update_leaf(2, "data3").unwrap();
+        assert_eq!(prover.index_of("duplicate"), Some(4));
+        assert_eq!(prover.index_of("data3"), Some(2));
+
+        // Pruned leaves are not found, kept ones are
+        let mut resumed = Prover::from_frontier(&prover.frontier(), 6).unwrap();
+        resumed.append(&["data7", "data1"]).unwrap();
+        assert_eq!(resumed.index_of("data1"), Some(7));
+        assert_eq!(resumed.index_of("data2"), None);
+    }
+
+    #[test]
+    fn test_new_deduped() {
+        // Large runs of identical leaves
+        let mut data = vec!["default"; 600];
+        data.extend(vec!["other"; 400]);
+        data[300] = "unique";
+
+        let naive_prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let deduped_prover = Prover::new_deduped(&data, 1).expect("Failed to create prover");
+        assert_eq!(
+            deduped_prover.get_root_hash().unwrap(),
+            naive_prover.get_root_hash().unwrap(),
+            "Deduplication must preserve the root"
+        );
+        for leaf_index in [0, 300, 599, 600, 999] {
+            assert_eq!(
+                deduped_prover
+                    .get_proof(leaf_index)
+                    .unwrap()
+                    .authentication_path,
+                naive_prover
+                    .get_proof(leaf_index)
+                    .unwrap()
+                    .authentication_path
+            );
+        }
+
+        // The naive construction hashes every leaf and every pair of nodes in the tree
+        let naive_hash_count = data.len()
+            + naive_prover
+                .levels
+                .iter()
+                .map(|level| level.len() / 2)
+                .sum::<usize>();
+
+        let (parallel_prover, deduped_hash_count) =
+            Prover::new_deduped_with_count(&data, 4).expect("Failed to create prover");
+        assert_eq!(
+            parallel_prover.get_root_hash(),
+            deduped_prover.get_root_hash()
+        );
+        assert!(
+            deduped_hash_count < naive_hash_count / 4,
+            "Deduplication computed {} of {} hashes",
+            deduped_hash_count,
+            naive_hash_count
+        );
+        assert_eq!(
+            Prover::new_deduped_with_count(&["a"; 4], 1).unwrap().1,
+            3,
+            "One leaf hash and one hash per level"
+        );
+    }
+
+    #[test]
+    fn test_from_frontier() {
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+
+        for size in [1, 2, 5, 8, 13, 999, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            let frontier = prover.frontier();
+            assert_eq!(frontier.len(), size.count_ones() as usize);
+
+            let resumed = Prover::from_frontier(&frontier, size).expect("Failed to resume");
+            assert_eq!(
+                resumed.get_root_hash().unwrap(),
+                prover.get_root_hash().unwrap(),
+                "Root mismatch for size {}",
+                size
+            );
+            assert_eq!(resumed.frontier(), frontier);
+
+            // Leaves below the frontier are pruned
+            if size > 1 {
+                assert!(resumed.get_proof(0).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_frontier_invalid() {
+        let frontier = [[1u8; 32], [2u8; 32]];
+        assert!(Prover::from_frontier(&frontier, 4).is_err());
+        assert!(Prover::from_frontier(&frontier, 0).is_err());
+        assert!(Prover::from_frontier(&frontier, 5).is_ok());
+    }
+
+    #[test]
+    fn test_leaf_count_and_height() {
+        for (data_length, expected_height) in [(1, 0), (4, 2), (5, 3), (1000, 10)] {
+            let large_data: Vec<String> = (0..data_length).map(|i| i.to_string()).collect();
+            let data_refs: Vec<&str> = large_data.iter().map(AsRef::as_ref).collect();
+            let prover = Prover::new(&data_refs, 1).expect("Failed to create prover");
+
+            assert_eq!(prover.leaf_count(), data_length);
+            assert_eq!(prover.height(), expected_height);
+            assert_eq!(
+                prover.height(),
+                prover.get_proof(0).unwrap().authentication_path.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_into_reuses_scratch() {
+        let first: Vec<&[u8]> = vec![b"data1", b"data2", b"data3", b"data4", b"data5"];
+        let second: Vec<&[u8]> = vec![b"other1", b"other2", b"other3"];
+        let mut scratch = Vec::new();
+
+        let prover = Prover::build_into(&first, 1, &mut scratch).unwrap();
+        let reference = Prover::from_bytes(&first, 1).unwrap();
+        assert_eq!(prover.levels, reference.levels);
+        assert_eq!(scratch, [reference.get_root_hash().unwrap()]);
+        let capacity = scratch.capacity();
+        let buffer = scratch.as_ptr();
+        assert!(capacity >= first.len());
+
+        // The second, smaller build fits in the buffer of the first one
+        let prover = Prover::build_into(&second, 2, &mut scratch).unwrap();
+        let reference = Prover::from_bytes(&second, 1).unwrap();
+        assert_eq!(prover.levels, reference.levels);
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+        assert_eq!(scratch.capacity(), capacity);
+        assert_eq!(scratch.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn test_append_matches_full_build() {
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+
+        for (first, second) in [
+            (1, 1),
+            (1, 4),
+            (3, 5),
+            (4, 4),
+            (5, 123),
+            (512, 488),
+            (999, 1),
+        ] {
+            let end = first + second;
+            let reference = Prover::new(&data[..end], 1).expect("Failed to create prover");
+
+            let mut prover = Prover::new(&data[..first], 1).expect("Failed to create prover");
+            prover.append(&data[first..end]).expect("Failed to append");
+
+            assert_eq!(prover.leaf_count(), end);
+            assert_eq!(
+                prover.get_root_hash().unwrap(),
+                reference.get_root_hash().unwrap(),
+                "Root mismatch after appending {} to {} leaves",
+                second,
+                first
+            );
+            for leaf_index in [0, first - 1, first, end - 1] {
+                assert_eq!(
+                    prover.get_proof(leaf_index).unwrap().authentication_path,
+                    reference.get_proof(leaf_index).unwrap().authentication_path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_append_in_steps() {
+        let data = vec![
+            "data1", "data2", "data3", "data4", "data5", "data6", "data7",
+        ];
+        let reference = Prover::new(&data, 1).expect("Failed to create prover");
+
+        let mut prover = Prover::new_retaining(&data[..2], 1).expect("Failed to create prover");
+        prover.append(&data[2..5]).unwrap();
+        prover.append(&data[5..]).unwrap();
+
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+        // Retained data grows with the tree
+        assert_eq!(
+            prover.get_proof_inline(6).unwrap().leaf_data.as_deref(),
+            Some("data7".as_bytes())
+        );
+
+        assert!(prover.append(&[]).is_err());
+    }
+
+    #[test]
+    fn test_append_many_matches_full_build() {
+        let data: Vec<String> = (0..10000).map(|i| format!("data{}", i)).collect();
+        let data: Vec<&str> = data.iter().map(String::as_str).collect();
+        let reference = Prover::new(&data, 1).expect("Failed to create prover");
+
+        // Appending one leaf at a time crosses every power of two up to 8192
+        let mut prover = Prover::new(&data[..1], 1).expect("Failed to create prover");
+        for leaf in &data[1..] {
+            prover.append(&[leaf]).unwrap();
+        }
+
+        assert_eq!(prover.levels, reference.levels);
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_append_after_from_frontier() {
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+        let reference = Prover::new(&data, 1).expect("Failed to create prover");
+
+        for size in [1, 6, 500, 512] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            let mut resumed = Prover::from_frontier(&prover.frontier(), size).unwrap();
+            resumed.append(&data[size..]).expect("Failed to append");
+
+            assert_eq!(
+                resumed.get_root_hash().unwrap(),
+                reference.get_root_hash().unwrap(),
+                "Root mismatch when resuming from {} leaves",
+                size
+            );
+            // New leaves can be proven, pruned ones cannot
+            assert_eq!(
+                resumed.get_proof(999).unwrap().authentication_path,
+                reference.get_proof(999).unwrap().authentication_path
+            );
+            if size > 1 {
+                assert!(resumed.get_proof(0).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_leaf_matches_full_build() {
+        let data = vec![
+            "data1", "data2", "data3", "data4", "data5", "data6", "data7",
+        ];
+
+        for leaf_index in 0..data.len() {
+            let mut prover = Prover::new_retaining(&data, 1).expect("Failed to create prover");
+            let root_hash = prover.update_leaf(leaf_index, "updated").unwrap();
+
+            let mut updated = data.clone();
+            updated[leaf_index] = "updated";
+            let reference = Prover::new(&updated, 1).expect("Failed to create prover");
+
+            assert_eq!(root_hash, reference.get_root_hash().unwrap());
+            assert_eq!(prover.get_root_hash().unwrap(), root_hash);
+            for i in 0..data.len() {
+                assert_eq!(
+                    prover.get_proof(i).unwrap().authentication_path,
+                    reference.get_proof(i).unwrap().authentication_path
+                );
+            }
+            assert_eq!(
+                prover
+                    .get_proof_inline(leaf_index)
+                    .unwrap()
+                    .leaf_data
+                    .as_deref(),
+                Some("updated".as_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_leaf_errors() {
+        let data = vec!["data1", "data2", "data3"];
+        let mut prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let root_hash = prover.get_root_hash().unwrap();
+
+        assert_eq!(
+            prover.update_leaf(3, "updated"),
+            Err(MerkleError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+
+        let mut resumed = Prover::from_frontier(&prover.frontier(), data.len()).unwrap();
+        assert_eq!(
+            resumed.update_leaf(0, "updated"),
+            Err(MerkleError::LeafPruned { index: 0 })
+        );
+        assert_eq!(resumed.get_root_hash().unwrap(), root_hash);
+    }
+
+    #[test]
+    fn test_root_if_changed_matches_update_leaf() {
+        let data = vec![
+            "data1", "data2", "data3", "data4", "data5", "data6", "data7",
+        ];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let root_hash = prover.get_root_hash().unwrap();
+
+        for leaf_index in 0..data.len() {
+            let hypothetical = prover.root_if_changed(leaf_index, "updated").unwrap();
+            assert_eq!(prover.get_root_hash().unwrap(), root_hash);
+
+            let mut updated = Prover::new(&data, 1).expect("Failed to create prover");
+            updated.update_leaf(leaf_index, "updated").unwrap();
+            assert_eq!(hypothetical, updated.get_root_hash().unwrap());
+        }
+
+        assert_eq!(
+            prover.root_if_changed(data.len(), "updated"),
+            Err(MerkleError::IndexOutOfBounds { index: 7, len: 7 })
+        );
+    }
+
+    #[test]
+    fn test_leaf_hashes() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        let leaf_hashes = prover.leaf_hashes();
+        assert_eq!(leaf_hashes.len(), prover.leaf_count());
+        for (i, leaf_hash) in leaf_hashes.iter().enumerate() {
+            assert_eq!(*leaf_hash, hash_leaf(data[i].as_bytes()));
+        }
+
+        let mut resumed = Prover::from_frontier(&prover.frontier(), data.len()).unwrap();
+        assert!(resumed.leaf_hashes().is_empty());
+        resumed.append(&["data6", "data7"]).unwrap();
+        assert_eq!(
+            resumed.leaf_hashes(),
+            vec![hash_leaf(b"data6"), hash_leaf(b"data7")]
+        );
+    }
+
+    #[test]
+    fn test_from_iter_matches_new() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
+        let owned: Vec<String> = data.iter().map(|d| d.to_string()).collect();
+
+        let prover = Prover::from_iter(owned, 2).expect("Failed to create prover");
+        let reference = Prover::new(&data, 2).expect("Failed to create prover");
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+        assert_eq!(prover.leaf_count(), data.len());
+
+        assert_eq!(
+            Prover::from_iter(Vec::new(), 1).err(),
+            Some(MerkleError::EmptyData)
+        );
+    }
+
+    #[test]
+    fn test_from_items() {
+        struct Transfer {
+            account: u32,
+            amount: u64,
+        }
+        let transfer_bytes =
+            |t: &Transfer| [&t.account.to_be_bytes()[..], &t.amount.to_be_bytes()].concat();
+
+        let transfers = [
+            Transfer {
+                account: 1,
+                amount: 100,
+            },
+            Transfer {
+                account: 2,
+                amount: 250,
+            },
+            Transfer {
+                account: 3,
+                amount: 75,
+            },
+        ];
+        let prover =
+            Prover::from_items(&transfers, transfer_bytes, 2).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        for (leaf_index, transfer) in transfers.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(proof.leaf_hash, hash_leaf(&transfer_bytes(transfer)));
+            assert!(verifier.verify_proof(&proof));
+        }
+
+        let encoded: Vec<Vec<u8>> = transfers.iter().map(transfer_bytes).collect();
+        let encoded: Vec<&[u8]> = encoded.iter().map(Vec::as_slice).collect();
+        let reference = Prover::from_bytes(&encoded, 1).expect("Failed to create prover");
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_with_decode_matches_decoded_data() {
+        let encoded: [&[u8]; 3] = [b"DATA1", b"DATA2", b"DATA3"];
+        let decode = |leaf: &[u8]| leaf.to_ascii_lowercase();
+
+        let prover = Prover::with_decode(&encoded, 2, decode).expect("Failed to create prover");
+        let reference = Prover::new(&["data1", "data2", "data3"], 1).unwrap();
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_append_with_checkpointing_resumes_after_restart() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().take(100).collect();
+        let reference = Prover::new(&data, 1).expect("Failed to create prover");
+        let path =
+            std::env::temp_dir().join(format!("merkletree-checkpoint-{}.bin", std::process::id()));
+
+        // Ingest the first 57 leaves, then crash without a final checkpoint
+        let mut prover = Prover::new(&data[..1], 1).expect("Failed to create prover");
+        let (tx, rx) = std::sync::mpsc::channel();
+        for leaf in &data[1..57] {
+            tx.send(leaf.to_string()).unwrap();
+        }
+        drop(tx);
+        prover.append_with_checkpointing(rx, 10, &path).unwrap();
+        drop(prover);
+
+        // The last checkpoint was written after 51 leaves; resume and replay from there
+        let mut resumed = Prover::from_checkpoint(&path).expect("Failed to resume");
+        assert_eq!(resumed.leaf_count(), 51);
+        let (tx, rx) = std::sync::mpsc::channel();
+        for leaf in &data[51..] {
+            tx.send(leaf.to_string()).unwrap();
+        }
+        drop(tx);
+        resumed.append_with_checkpointing(rx, 10, &path).unwrap();
+
+        assert_eq!(resumed.leaf_count(), data.len());
+        assert_eq!(
+            resumed.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+
+        resumed.write_checkpoint(&path).unwrap();
+        let restored = Prover::from_checkpoint(&path).expect("Failed to resume");
+        assert_eq!(
+            restored.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_errors() {
+        let mut prover = Prover::new(&["data1"], 1).expect("Failed to create prover");
+        let (_tx, rx) = std::sync::mpsc::channel();
+        let path = std::env::temp_dir().join(format!(
+            "merkletree-checkpoint-errors-{}.bin",
+            std::process::id()
+        ));
+        assert_eq!(
+            prover.append_with_checkpointing(rx, 0, &path),
+            Err(MerkleError::ZeroCheckpointInterval)
+        );
+
+        assert_eq!(
+            Prover::from_checkpoint(&path).err(),
+            Some(MerkleError::Io(std::io::ErrorKind::NotFound))
+        );
+        fs::write(&path, [3, 0, 0, 0, 0, 0, 0, 0, 1]).unwrap();
+        assert_eq!(
+            Prover::from_checkpoint(&path).err(),
+            Some(MerkleError::MalformedCheckpoint(
+                "Frontier is not a whole number of hashes"
+            ))
+        );
+        fs::remove_file(&path).unwrap();
     }
 
-    /// Generates a Merkle proof for the specified leaf index.
-    ///
-    /// # Arguments
-    ///
-    /// * `leaf_index` - The index of the leaf for which the proof should be generated.
-    ///
-    /// # Returns
-    ///
-    /// A Result containing the generated MerkleProof, or an error string if any issues arise.
-    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, &'static str> {
-        if leaf_index >= self.data_length {
-            return Err("Leaf index is out of bounds.");
-        }
+    #[test]
+    fn test_serialize_round_trip() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
 
-        let mut authentication_path = Vec::new();
-        let mut height: usize = (self.data_length as f64).log2().ceil() as usize;
-        let mut current_node = self.root.as_ref().unwrap(); // Assuming root is always present
+        for size in [1, 2, 3, 7, 8, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            let restored = Prover::deserialize(&prover.serialize()).expect("Failed to restore");
 
-        while height > 0 {
-            // Take hash of left sibling and go to right subtree
-            if ((1 << (height - 1)) & leaf_index) != 0 {
-                authentication_path.push(current_node.left.as_ref().unwrap().hash);
-                current_node = current_node.right.as_ref().unwrap();
-            }
-            // Take hash of right sibling and go to left subtree
-            else {
-                authentication_path.push(current_node.right.as_ref().unwrap().hash);
-                current_node = current_node.left.as_ref().unwrap();
+            assert_eq!(restored.leaf_count(), size);
+            assert_eq!(restored.root(), prover.root());
+            for leaf_index in 0..size {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                let restored_proof = restored.get_proof(leaf_index).unwrap();
+                assert_eq!(restored_proof.to_bytes(), proof.to_bytes());
             }
-            height -= 1;
         }
 
-        Ok(MerkleProof {
-            leaf_index: leaf_index,
-            leaf_hash: current_node.hash,
-            authentication_path,
-        })
+        // Retained data and the pruned nodes of a Prover created from a frontier are kept
+        let prover = Prover::new_retaining(&data[..5], 1).unwrap();
+        let restored = Prover::deserialize(&prover.serialize()).unwrap();
+        assert_eq!(
+            restored.get_proof_inline(3).unwrap().leaf_data,
+            prover.get_proof_inline(3).unwrap().leaf_data
+        );
+
+        let mut resumed = Prover::from_frontier(&prover.frontier(), 5).unwrap();
+        resumed.append(&data[5..11]).unwrap();
+        let mut restored = Prover::deserialize(&resumed.serialize()).unwrap();
+        assert_eq!(restored.root(), resumed.root());
+        assert_eq!(restored.get_proof(1).err(), resumed.get_proof(1).err());
+        assert_eq!(
+            restored.get_proof(8).unwrap().to_bytes(),
+            resumed.get_proof(8).unwrap().to_bytes()
+        );
+        restored.append(&data[11..20]).unwrap();
+        assert_eq!(restored.root(), Prover::new(&data[..20], 1).unwrap().root());
+
+        // States of version 1 and 2 have no maximum, and version 1 has no pairing byte
+        let prover = Prover::new(&data[..5], 1).unwrap();
+        let bytes = prover.serialize();
+        let mut legacy = [&bytes[..21], &bytes[29..bytes.len() - 32]].concat();
+        legacy[4] = 2;
+        let mut version_2 = legacy.clone();
+        version_2.extend(hash_data_sequences(&[&version_2]));
+        assert_eq!(Prover::deserialize(&version_2).unwrap().serialize(), bytes);
+        legacy.pop();
+        legacy[4] = 1;
+        legacy.extend(hash_data_sequences(&[&legacy]));
+        let restored = Prover::deserialize(&legacy).unwrap();
+        assert_eq!(restored.serialize(), bytes);
     }
 
-    /// Constructs the Merkle tree from the provided data.
-    ///
-    /// Internal method used during the creation of the Prover instance.
-    ///
-    /// # Arguments
-    ///
-    /// * `data` - A slice of string data from which to construct the tree.
-    /// * `_num_threads` - The number of threads to be used for parallel construction.
-    ///
-    /// # Returns
-    ///
-    /// An Option containing the root node of the constructed tree.
-    fn build_tree(data: &[&str], _num_threads: usize) -> Option<Box<Node>> {
-        // Use the input data to create the leaf nodes
-        // Convert the input string slice to a byte slice
-        let mut current_level: Vec<Option<Box<Node>>> = data
-            .iter()
-            .map(|d| {
-                Some(Box::new(Node {
-                    hash: hash_data_sequences(&[d.as_bytes()]), // Make to bytes and wrap in slice
-                    left: None,
-                    right: None,
-                }))
-            })
-            .collect();
+    #[test]
+    fn test_max_leaves_survives_serialization() {
+        // A tree above the default maximum, cheaply created from a frontier
+        let size = MAX_DATA_SIZE + 3;
+        let frontier = [
+            hash_leaf(b"left"),
+            hash_leaf(b"middle"),
+            hash_leaf(b"right"),
+        ];
+        let prover = Prover::from_frontier_with_max(&frontier, size, 2 * MAX_DATA_SIZE).unwrap();
 
-        // While a level has more than one node, create the parent level
-        while current_level.len() > 1 {
-            if current_level.len() % 2 == 1 {
-                // If there is a uneven number of nodes in current level,
-                // create a new node with the same hash value
-                let new_node = Box::new(Node {
-                    hash: current_level.last().unwrap().as_ref().unwrap().hash.clone(),
-                    left: None,
-                    right: None,
-                });
-                current_level.push(Some(new_node));
-            }
+        let mut restored = Prover::deserialize(&prover.serialize()).expect("Failed to restore");
+        assert_eq!(restored.leaf_count(), size);
+        assert_eq!(restored.root(), prover.root());
+        assert_eq!(restored.max_leaves, 2 * MAX_DATA_SIZE);
+        restored.append(&["data1"]).unwrap();
 
-            // The size of the next (upper) level will be half the size
-            let next_size = current_level.len() / 2;
+        let path = std::env::temp_dir().join(format!(
+            "merkletree-checkpoint-max-{}.bin",
+            std::process::id()
+        ));
+        restored.write_checkpoint(&path).unwrap();
+        let mut resumed = Prover::from_checkpoint(&path).expect("Failed to resume");
+        assert_eq!(resumed.root(), restored.root());
+        resumed.append(&["data2"]).unwrap();
+        fs::remove_file(&path).unwrap();
 
-            // Fill the vector with 'None' so it can index into.
-            // All None values will be overwritten.
-            let mut next_level: Vec<Option<Box<Node>>> = (0..next_size).map(|_| None).collect();
+        // A smaller maximum is kept as well
+        let mut restored = Prover::deserialize(
+            &Prover::builder()
+                .data(&["data1", "data2", "data3"])
+                .max_leaves(4)
+                .build()
+                .unwrap()
+                .serialize(),
+        )
+        .unwrap();
+        restored.append(&["data4"]).unwrap();
+        assert_eq!(
+            restored.append(&["data5"]),
+            Err(MerkleError::DataTooLarge { len: 5, max: 4 })
+        );
+    }
 
-            // Collect the result as a vector of (index, Option<Box<Node>>)
-            let parents: Vec<(usize, Option<Box<Node>>)> = current_level
-                .par_chunks_exact_mut(2)
-                .enumerate()
-                .map(|(chunk_number, chunk)| {
-                    let combined_hash = hash_data_sequences(&[
-                        &chunk[0].as_ref().unwrap().hash,
-                        &chunk[1].as_ref().unwrap().hash,
-                    ]);
+    #[test]
+    fn test_deserialize_rejects_corrupted_state() {
+        let prover = Prover::new(&["data1", "data2", "data3"], 1).unwrap();
+        let bytes = prover.serialize();
 
-                    let parent = Box::new(Node {
-                        hash: combined_hash,
-                        left: chunk[0].take(),
-                        right: chunk[1].take(),
-                    });
+        // Any flipped bit is caught by the checksum
+        for position in [0, 4, 5, 13, 21, 60, bytes.len() - 1] {
+            let mut corrupted = bytes.clone();
+            corrupted[position] ^= 1;
+            assert_eq!(
+                Prover::deserialize(&corrupted).err(),
+                Some(MerkleError::MalformedState("Checksum does not match"))
+            );
+        }
+        assert!(Prover::deserialize(&bytes[..bytes.len() - 1]).is_err());
 
-                    (chunk_number, Some(parent))
-                })
-                .collect();
+        // A state with a valid checksum but a wrong header or length is rejected too
+        let with_checksum = |mut state: Vec<u8>| {
+            let checksum = hash_data_sequences(&[&state]);
+            state.extend(checksum);
+            state
+        };
+        let state = &bytes[..bytes.len() - 32];
+        let mut wrong_magic = state.to_vec();
+        wrong_magic[0] = b'X';
+        assert_eq!(
+            Prover::deserialize(&with_checksum(wrong_magic)).err(),
+            Some(MerkleError::MalformedState("Missing magic bytes"))
+        );
+        let mut wrong_version = state.to_vec();
+        wrong_version[4] = STATE_VERSION + 1;
+        assert_eq!(
+            Prover::deserialize(&with_checksum(wrong_version)).err(),
+            Some(MerkleError::MalformedState("Unsupported version"))
+        );
+        assert_eq!(
+            Prover::deserialize(&with_checksum(state[..state.len() - 33].to_vec())).err(),
+            Some(MerkleError::MalformedState("Serialized state is truncated"))
+        );
+        let mut trailing = state.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            Prover::deserialize(&with_checksum(trailing)).err(),
+            Some(MerkleError::MalformedState(
+                "Trailing bytes after the state"
+            ))
+        );
+    }
 
-            for (idx, parent) in parents.into_iter() {
-                next_level[idx] = parent;
-            }
+    #[test]
+    fn test_root_matches_get_root_hash() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
 
-            current_level = next_level;
+        for size in [1, 2, 3, 7, 8, 100, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            assert_eq!(prover.root(), prover.get_root_hash().unwrap());
         }
 
-        // Return the root node
-        current_level.pop().unwrap()
+        let resumed = Prover::from_frontier(&Prover::new(&data[..5], 1).unwrap().frontier(), 5)
+            .expect("Failed to create prover");
+        assert_eq!(resumed.root(), resumed.get_root_hash().unwrap());
     }
 
-    pub fn generate_proof(_target: &str) -> MerkleProof {
-        unimplemented!()
-    }
-}
+    #[test]
+    fn test_root_from_data_matches_prover() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::hasher::hash_data_sequences;
-    use std::fs;
+        for size in [1, 2, 3, 5, 8, 13, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            assert_eq!(root_from_data(&data[..size], 2).unwrap(), prover.root());
+        }
+
+        assert_eq!(root_from_data(&[], 1), Err(MerkleError::EmptyData));
+        assert_eq!(root_from_data(&data, 0), Err(MerkleError::ZeroThreads));
+    }
 
     #[test]
-    fn test_construct_and_get_root_hash() {
-        let data = vec!["data1", "data2", "data3", "data4", "data5"];
-        let num_threads = 1;
-        let root_hash;
+    fn test_proof_len_for_bounds_serialized_len() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
 
-        // Construct the initial prover and get its root hash
-        match Prover::new(&data, num_threads) {
-            Ok(prover) => {
-                match prover.get_root_hash() {
-                    Ok(hash) => {
-                        assert!(!hash.is_empty(), "Root hash should not be empty");
-                        root_hash = hash; // Assigning the hash to root_hash for use later
-                    }
-                    Err(e) => {
-                        panic!("Failed to get root hash: {}", e);
-                    }
-                }
-            }
-            Err(e) => {
-                panic!("Failed to create Prover instance: {}", e);
-            }
-        }
+        for size in [1, 2, 5, 8, 13, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            let max_len = prover.proof_len_for();
+            assert_eq!(
+                prover.get_proof(0).unwrap().authentication_path.len(),
+                max_len
+            );
 
-        // Construct a second prover and compare its root hash to the first
-        match Prover::new(&data, num_threads) {
-            Ok(prover2) => match prover2.get_root_hash() {
-                Ok(hash2) => {
-                    assert_eq!(
-                        root_hash, hash2,
-                        "Root hashes should match for the same data"
-                    );
-                }
-                Err(e) => {
-                    panic!("Failed to get root hash from prover2: {}", e);
-                }
-            },
-            Err(e) => {
-                panic!("Failed to create Prover2 instance: {}", e);
+            for leaf_index in 0..size {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                assert!(proof.authentication_path.len() <= max_len);
+                assert_eq!(proof.serialized_len(), proof.to_bytes().len());
+                assert!(proof.serialized_len() <= 48 + 32 * max_len);
             }
         }
+    }
 
-        // Construct a prover with modified data and ensure its root hash is different
-        let mut modified_data = data.clone();
-        modified_data[0] = "modified_data";
+    #[test]
+    fn test_path_len_of() {
+        let contents = fs::read_to_string("tests/data/data1000.txt").unwrap();
+        let data: Vec<&str> = contents.lines().collect();
 
-        match Prover::new(&modified_data, num_threads) {
-            Ok(prover_modified) => match prover_modified.get_root_hash() {
-                Ok(modified_hash) => {
-                    assert_ne!(
-                        root_hash, modified_hash,
-                        "Root hash should change when data is modified"
-                    );
-                }
-                Err(e) => {
-                    panic!("Failed to get root hash from modified prover: {}", e);
-                }
-            },
-            Err(e) => {
-                panic!("Failed to create Prover with modified data instance: {}", e);
+        for size in [1, 2, 5, 8, 13, 1000] {
+            let prover = Prover::new(&data[..size], 1).expect("Failed to create prover");
+            for leaf_index in 0..size {
+                let len = prover.path_len_of(leaf_index).unwrap();
+                let proof = prover.get_proof(leaf_index).unwrap();
+                assert_eq!(proof.authentication_path.len(), len);
+                assert!(len <= prover.proof_len_for());
             }
+            assert_eq!(
+                prover.path_len_of(size),
+                Err(MerkleError::IndexOutOfBounds {
+                    index: size,
+                    len: size
+                })
+            );
         }
+
+        // Without promotion, every path has the length of the tree height
+        let prover = Prover::builder()
+            .data(&data[..5])
+            .pairing(PairingPolicy::Duplicate)
+            .build()
+            .expect("Failed to create prover");
+        assert_eq!(prover.path_len_of(4), Ok(3));
+        assert_eq!(prover.get_proof(4).unwrap().authentication_path.len(), 3);
     }
 
     #[test]
-    fn test_construct_and_get_root_hash_parallel() {
+    fn test_new_unique() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let prover = Prover::new_unique(&data, 1).expect("Failed to create prover");
+        assert_eq!(prover.root(), Prover::new(&data, 1).unwrap().root());
+
+        let data = vec!["data1", "data2", "data3", "data2", "data1"];
+        assert_eq!(
+            Prover::new_unique(&data, 1).err(),
+            Some(MerkleError::DuplicateLeaf {
+                index: 3,
+                original: 1
+            })
+        );
+        assert!(Prover::new(&data, 1).is_ok());
+    }
+
+    #[test]
+    fn test_from_leaf_hashes() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let hashes: Vec<[u8; 32]> = data.iter().map(|d| hash_leaf(d.as_bytes())).collect();
+
+        let prover = Prover::from_leaf_hashes(&hashes, 2).expect("Failed to create prover");
+        let reference = Prover::new(&data, 1).expect("Failed to create prover");
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            reference.get_root_hash().unwrap()
+        );
+
+        // The supplied hashes are the leaves themselves
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+        let proof = prover.get_proof(3).unwrap();
+        assert_eq!(proof.leaf_hash, hashes[3]);
+        assert!(verifier.verify_proof(&proof));
+
+        assert!(Prover::from_leaf_hashes(&[], 1).is_err());
+        assert!(Prover::from_leaf_hashes(&hashes, 0).is_err());
+        assert!(Prover::from_leaf_hashes(&vec![[0u8; 32]; MAX_DATA_SIZE + 1], 1).is_err());
+    }
+
+    #[test]
+    fn test_roots_and_proofs_match_reference() {
+        // Root hashes and digests of all proofs, as produced by the pointer-based tree
+        let expected = [
+            (
+                1,
+                "25d393ccd026153f02944922fa7e972b746166698bbf8d9b20b04ec237c39e33",
+                "efd7363c2f59f58035322d97f4fd5225a4ee3a070b756b3e354a0b77cd23d7c0",
+            ),
+            (
+                2,
+                "049e10158f011c35b22523079fd582495a7d6619712fd4846775a9a5eff359a3",
+                "99cbd19617debc80553ed9804f4fb9c00c31384873534fefd51ccc087a818111",
+            ),
+            (
+                3,
+                "9dd422a8241b86b5a9709d27f0b8ce59a65c3bb6475a98a8f719f4cfc4400e15",
+                "bffa55df723918b510749cde61f4bb0ec8386421a13bcd76c81370ee0f0b5032",
+            ),
+            (
+                5,
+                "d77e67a997938f65c7928817af99a2dab40acd983f84fb7b6db34e54034bcb49",
+                "035fc130907a8c6416051336564038213d98d0ebff879db1be8cccebb18596dd",
+            ),
+            (
+                8,
+                "5b82cd1629e80f280585a8c3489543b6c14526c040621a3b6db4d32184c5ff66",
+                "3931390a65084bba836235350e860ef3c541422555fd70a71cce65b45d1e2f5b",
+            ),
+            (
+                13,
+                "2d1416bcfe26e9a46011170fd2edd486fe1b9a6981722f9637c0392581a805bc",
+                "3e080784cc3e0d5948bf921a11438a2ceec7c0ae7e96af97f1126082628d921e",
+            ),
+            (
+                1000,
+                "d6e53ed2f775c6808569bb00195312b79990a7700f973231a485da7803603f78",
+                "731b16ac1fca5b144b7e9cbf52f89d8762c63ac819b0a033462e215ca17c5737",
+            ),
+        ];
+
         // Read data from file
         let content =
             fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
         let data: Vec<&str> = content.lines().collect();
+        let to_hex =
+            |hash: [u8; 32]| -> String { hash.iter().map(|b| format!("{:02x}", b)).collect() };
 
-        let mut reference_root_hash: Option<[u8; 32]> = None;
-
-        // Testing num_threads from 1 to 8
-        for num_threads in 1..=8 {
-            match Prover::new(&data, num_threads) {
-                Ok(prover) => match prover.get_root_hash() {
-                    Ok(hash) => {
-                        assert!(
-                            !hash.is_empty(),
-                            "Root hash should not be empty with {} threads",
-                            num_threads
-                        );
+        for (size, root_hash, proofs_digest) in expected {
+            let prover = Prover::new(&data[..size], 4).expect("Failed to create prover");
+            assert_eq!(to_hex(prover.get_root_hash().unwrap()), root_hash);
 
-                        if let Some(ref root) = reference_root_hash {
-                            assert_eq!(
-                                &hash, root,
-                                "Root hash mismatch at {} threads!",
-                                num_threads
-                            );
-                        } else {
-                            reference_root_hash = Some(hash);
-                        }
-                    }
-                    Err(e) => {
-                        panic!(
-                            "Failed to get root hash with {} threads: {}",
-                            num_threads, e
-                        );
-                    }
-                },
-                Err(e) => {
-                    panic!(
-                        "Failed to create Prover instance with {} threads: {}",
-                        num_threads, e
-                    );
-                }
-            }
+            let proofs: Vec<u8> = (0..size)
+                .flat_map(|leaf_index| prover.get_proof(leaf_index).unwrap().to_bytes())
+                .collect();
+            assert_eq!(
+                to_hex(hash_data_sequences(&[&proofs])),
+                proofs_digest,
+                "Proofs differ for size {}",
+                size
+            );
         }
     }
 
     #[test]
-    fn test_get_proof_complete_tree() {
-        let data = vec!["data1", "data2", "data3", "data4"];
-        let num_threads = 1;
-        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
-
-        for leaf_index in 0..data.len() {
-            let proof = prover.get_proof(leaf_index).unwrap();
+    fn test_parallel_leaf_hashing_matches_sequential() {
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+        let sequential: Vec<[u8; 32]> = data.iter().map(|d| hash_leaf(d.as_bytes())).collect();
 
-            assert_eq!(proof.leaf_index, leaf_index);
+        let prover = Prover::new(&data, 8).expect("Failed to create prover");
+        assert_eq!(prover.levels[0], sequential, "Leaf order must be preserved");
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            Prover::from_leaf_hashes(&sequential, 1)
+                .unwrap()
+                .get_root_hash()
+                .unwrap()
+        );
 
-            assert_eq!(
-                proof.leaf_hash,
-                hash_data_sequences(&[data[leaf_index].as_bytes()])
-            );
+        // A single worker thread hashes the leaves one after another
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let single_threaded =
+            pool.install(|| Prover::new(&data, 1).expect("Failed to create prover"));
+        assert_eq!(
+            single_threaded.get_root_hash().unwrap(),
+            prover.get_root_hash().unwrap()
+        );
+    }
 
-            // The height of the tree should be ceil(log2(4)) = 2
+    #[test]
+    fn test_num_threads_bounds_thread_pool() {
+        for num_threads in [1, 2, 3] {
             assert_eq!(
-                proof.authentication_path.len(),
-                2,
-                "Failed at leaf_index: {}",
-                leaf_index
+                Prover::in_thread_pool(num_threads, rayon::current_num_threads),
+                Ok(num_threads)
             );
         }
+
+        // Read data from file
+        let content =
+            fs::read_to_string("tests/data/data10000.txt").expect("Failed to read the file");
+        let data: Vec<&str> = content.lines().collect();
+        let leaf_hashes: Vec<[u8; 32]> = data.iter().map(|d| hash_leaf(d.as_bytes())).collect();
+
+        // Building in rayon's global pool gives the same root as in a pool of 2 threads
+        let default_levels = Prover::levels_from_leaf_hashes(leaf_hashes);
+        let prover = Prover::new(&data, 2).expect("Failed to create prover");
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            default_levels.last().unwrap()[0]
+        );
     }
 
     #[test]
-    fn test_get_proof_non_complete_tree() {
-        let data = vec!["data1", "data2", "data3", "data4", "data5"];
-        let num_threads = 1;
-        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+    fn test_path_length() {
+        // Leaves of a perfect tree all have paths as long as the tree is high
+        assert!((0..8).all(|leaf_index| path_length(leaf_index, 8) == 3));
 
-        for leaf_index in 0..data.len() {
-            let proof = prover.get_proof(leaf_index).unwrap();
+        // In a tree of 6 leaves, the last two leaves are promoted past level 1
+        let lengths: Vec<usize> = (0..6)
+            .map(|leaf_index| path_length(leaf_index, 6))
+            .collect();
+        assert_eq!(lengths, [3, 3, 3, 3, 2, 2]);
 
-            assert_eq!(proof.leaf_index, leaf_index);
+        assert_eq!(path_length(0, 1), 0);
+        assert_eq!(path_length(4, 5), 1);
+    }
 
-            assert_eq!(
-                proof.leaf_hash,
-                hash_data_sequences(&[data[leaf_index].as_bytes()])
-            );
+    #[test]
+    fn test_odd_levels_promote_last_node() {
+        let data = vec!["data1", "data2", "data3"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
 
-            // The height of the tree should be ceil(log2(5)) = 3
-            assert_eq!(
-                proof.authentication_path.len(),
-                3,
-                "Failed at leaf_index: {}",
-                leaf_index
-            );
-        }
+        // The third leaf is carried up unchanged instead of being hashed with a copy of itself
+        let left = hash_node(&hash_leaf(b"data1"), &hash_leaf(b"data2"));
+        assert_eq!(
+            prover.get_root_hash().unwrap(),
+            hash_node(&left, &hash_leaf(b"data3"))
+        );
+        assert_eq!(prover.get_proof(2).unwrap().authentication_path, vec![left]);
     }
 
     #[test]
-    fn test_get_proof_out_of_bounds() {
-        let data = vec!["data1", "data2", "data3", "data4"];
-        let num_threads = 1;
-        let prover = Prover::new(&data, num_threads).expect("Failed to create prover");
+    fn test_tree_height() {
+        let expected_heights = [
+            (1, 0),
+            (2, 1),
+            (3, 2),
+            (4, 2),
+            (1_048_575, 20),
+            (1_048_576, 20),
+        ];
 
-        // Attempt to get a proof for an out-of-bounds leaf_index
-        let result = prover.get_proof(data.len());
+        for (data_length, expected_height) in expected_heights {
+            assert_eq!(tree_height(data_length), expected_height);
 
-        // Validate the result is an error
-        assert!(
-            result.is_err(),
-            "Expected an error for out-of-bounds leaf_index"
-        );
+            // The computed height must match the depth of the tree actually built
+            let large_data: Vec<String> = (0..data_length).map(|i| i.to_string()).collect();
+            let data_refs: Vec<&str> = large_data.iter().map(AsRef::as_ref).collect();
+            let prover = Prover::new(&data_refs, 1).expect("Failed to create prover");
+
+            // The first leaf is never promoted, so its path is as long as the tree is high
+            assert_eq!(
+                prover.get_proof(0).unwrap().authentication_path.len(),
+                expected_height
+            );
+            for leaf_index in [0, data_length / 2, data_length - 1] {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                assert_eq!(
+                    proof.authentication_path.len(),
+                    path_length(leaf_index, data_length),
+                    "Failed for data_length {} at leaf_index {}",
+                    data_length,
+                    leaf_index
+                );
+            }
+        }
     }
 
     #[test]
@@ -382,9 +3921,12 @@ mod tests {
         let data_refs: Vec<&str> = large_data.iter().map(AsRef::as_ref).collect();
 
         let result = Prover::new(&data_refs, 1);
-        assert!(
-            result.is_err(),
-            "Data size exceeds the maximum allowed size"
+        assert_eq!(
+            result.err(),
+            Some(MerkleError::DataTooLarge {
+                len: MAX_DATA_SIZE + 1,
+                max: MAX_DATA_SIZE
+            })
         );
     }
 
@@ -393,10 +3935,43 @@ mod tests {
         let large_data: Vec<String> = (0..MAX_DATA_SIZE).map(|i| i.to_string()).collect();
         let data_refs: Vec<&str> = large_data.iter().map(AsRef::as_ref).collect();
 
+        // The limit is inclusive
         let result = Prover::new(&data_refs, 1);
         assert!(
             result.is_ok(),
             "Data size should be within the allowable limit"
         );
+        assert_eq!(result.unwrap().leaf_count(), MAX_DATA_SIZE);
+    }
+
+    #[test]
+    fn test_max_data_size_boundary() {
+        let leaf_hashes = vec![[0u8; 32]; MAX_DATA_SIZE + 1];
+
+        let mut prover = Prover::from_leaf_hashes(&leaf_hashes[..MAX_DATA_SIZE - 1], 1)
+            .expect("Data size below the limit should be accepted");
+        assert_eq!(prover.leaf_count(), MAX_DATA_SIZE - 1);
+
+        // Growing up to the limit succeeds, and beyond it fails without changing the tree
+        prover.append(&["last"]).unwrap();
+        assert_eq!(prover.leaf_count(), MAX_DATA_SIZE);
+        let root = prover.root();
+        assert_eq!(
+            prover.append(&["one too many"]),
+            Err(MerkleError::DataTooLarge {
+                len: MAX_DATA_SIZE + 1,
+                max: MAX_DATA_SIZE
+            })
+        );
+        assert_eq!(prover.leaf_count(), MAX_DATA_SIZE);
+        assert_eq!(prover.root(), root);
+
+        assert_eq!(
+            Prover::from_leaf_hashes(&leaf_hashes, 1).err(),
+            Some(MerkleError::DataTooLarge {
+                len: MAX_DATA_SIZE + 1,
+                max: MAX_DATA_SIZE
+            })
+        );
     }
 }
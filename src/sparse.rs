@@ -0,0 +1,295 @@
+//! Sparse Merkle trees keyed by 256-bit keys.
+//!
+//! A sparse Merkle tree has a leaf for every possible key, almost all of which are empty.
+//! Subtrees that contain only empty leaves share the same hash at each level, so these
+//! default hashes can be precomputed once and cached.
+//!
+//! The bits of a key, most significant bit of the first byte first, select the path from the
+//! root down to its leaf. A `SparseProof` proves either that a key holds a value or that its
+//! leaf is empty.
+
+use std::collections::HashMap;
+
+use crate::hasher::{hash_data_sequences, hash_leaf, hash_node, LEAF_PREFIX};
+
+/// The number of levels between the root and the leaves of a `SparseMerkleTree`.
+pub const SPARSE_DEPTH: usize = 256;
+
+/// Computes the hashes of empty subtrees for every level up to `depth`.
+///
+/// Element 0 is the hash of an empty leaf, and element `i + 1` is the hash of an internal
+/// node whose two children are both empty subtrees of level `i`.
+///
+/// # Arguments
+///
+/// * `depth` - The height of the highest empty subtree to compute.
+///
+/// # Returns
+///
+/// A vector of `depth + 1` hashes, indexed by level.
+pub fn default_hashes(depth: usize) -> Vec<[u8; 32]> {
+    let mut hashes = Vec::with_capacity(depth + 1);
+    hashes.push(hash_leaf(&[]));
+    for level in 0..depth {
+        hashes.push(hash_node(&hashes[level], &hashes[level]));
+    }
+    hashes
+}
+
+/// Computes the hash of a non-empty leaf, binding the value to its key.
+fn sparse_leaf_hash(key: &[u8; 32], value: &[u8]) -> [u8; 32] {
+    hash_data_sequences(&[&[LEAF_PREFIX], key, value])
+}
+
+/// Returns whether the node at `level` on the path of `key` is a right child.
+fn is_right_child(key: &[u8; 32], level: usize) -> bool {
+    let bit = SPARSE_DEPTH - 1 - level;
+    (key[bit / 8] >> (7 - bit % 8)) & 1 == 1
+}
+
+/// Clears the last `level` bits of `key`, identifying the node at `level` above its leaf.
+fn key_prefix(key: &[u8; 32], level: usize) -> [u8; 32] {
+    let mut prefix = *key;
+    for bit in SPARSE_DEPTH - level..SPARSE_DEPTH {
+        prefix[bit / 8] &= !(0x80 >> (bit % 8));
+    }
+    prefix
+}
+
+/// A proof that a key holds a given value in a `SparseMerkleTree`, or that it holds none.
+pub struct SparseProof {
+    pub key: [u8; 32],
+    /// The value of the key, or `None` for a proof of non-inclusion.
+    pub value: Option<Vec<u8>>,
+    /// The sibling hashes ordered from the root down to the leaf.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// A Merkle tree with one leaf for each 256-bit key.
+///
+/// Only the nodes above non-empty leaves are stored; every other node is an empty subtree
+/// whose hash is taken from the precomputed default hashes.
+pub struct SparseMerkleTree {
+    values: HashMap<[u8; 32], Vec<u8>>,
+    nodes: HashMap<(usize, [u8; 32]), [u8; 32]>,
+    defaults: Vec<[u8; 32]>,
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseMerkleTree {
+    /// Creates a sparse Merkle tree in which every leaf is empty.
+    pub fn new() -> Self {
+        SparseMerkleTree {
+            values: HashMap::new(),
+            nodes: HashMap::new(),
+            defaults: default_hashes(SPARSE_DEPTH),
+        }
+    }
+
+    /// Sets the value of a key, replacing any previous value, and rehashes its path.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key addressing the leaf.
+    /// * `value` - The value to store at the leaf.
+    pub fn insert(&mut self, key: [u8; 32], value: &[u8]) {
+        let mut node = sparse_leaf_hash(&key, value);
+        self.values.insert(key, value.to_vec());
+        self.nodes.insert((0, key), node);
+
+        for level in 0..SPARSE_DEPTH {
+            let sibling = self.sibling(&key, level);
+            node = if is_right_child(&key, level) {
+                hash_node(&sibling, &node)
+            } else {
+                hash_node(&node, &sibling)
+            };
+            self.nodes
+                .insert((level + 1, key_prefix(&key, level + 1)), node);
+        }
+    }
+
+    /// Retrieves the value of a key.
+    ///
+    /// # Returns
+    ///
+    /// The value stored at the key, or `None` if its leaf is empty.
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8]> {
+        self.values.get(key).map(Vec::as_slice)
+    }
+
+    /// Retrieves the root hash of the tree.
+    pub fn root(&self) -> [u8; 32] {
+        self.node(SPARSE_DEPTH, &[0; 32])
+    }
+
+    /// Generates a proof of the value of a key, or of its absence.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to prove.
+    ///
+    /// # Returns
+    ///
+    /// A `SparseProof` of inclusion if the key holds a value, otherwise of non-inclusion.
+    pub fn prove(&self, key: &[u8; 32]) -> SparseProof {
+        let siblings = (0..SPARSE_DEPTH)
+            .rev()
+            .map(|level| self.sibling(key, level))
+            .collect();
+        SparseProof {
+            key: *key,
+            value: self.values.get(key).cloned(),
+            siblings,
+        }
+    }
+
+    /// Retrieves the node at `level` whose subtree contains `key`.
+    fn node(&self, level: usize, key: &[u8; 32]) -> [u8; 32] {
+        self.nodes
+            .get(&(level, key_prefix(key, level)))
+            .copied()
+            .unwrap_or(self.defaults[level])
+    }
+
+    /// Retrieves the sibling of the node at `level` on the path of `key`.
+    fn sibling(&self, key: &[u8; 32], level: usize) -> [u8; 32] {
+        let mut sibling_key = *key;
+        let bit = SPARSE_DEPTH - 1 - level;
+        sibling_key[bit / 8] ^= 0x80 >> (bit % 8);
+        self.node(level, &sibling_key)
+    }
+}
+
+/// Verifies a proof of inclusion or non-inclusion against the root of a `SparseMerkleTree`.
+///
+/// # Arguments
+///
+/// * `root` - The expected root hash of the tree.
+/// * `proof` - The proof to verify.
+///
+/// # Returns
+///
+/// Returns `true` if the key holds `proof.value` in the tree with root `root`, or its leaf is
+/// empty when `proof.value` is `None`, otherwise returns `false`.
+pub fn verify_sparse_proof(root: [u8; 32], proof: &SparseProof) -> bool {
+    if proof.siblings.len() != SPARSE_DEPTH {
+        return false;
+    }
+
+    let mut node = match &proof.value {
+        Some(value) => sparse_leaf_hash(&proof.key, value),
+        None => hash_leaf(&[]),
+    };
+    for (level, sibling) in proof.siblings.iter().rev().enumerate() {
+        node = if is_right_child(&proof.key, level) {
+            hash_node(sibling, &node)
+        } else {
+            hash_node(&node, sibling)
+        };
+    }
+    node == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_hashes() {
+        let depth = 16;
+        let hashes = default_hashes(depth);
+
+        assert_eq!(hashes.len(), depth + 1);
+        assert_eq!(
+            hashes[0],
+            hash_leaf(&[]),
+            "Level 0 should be the empty leaf"
+        );
+
+        for level in 1..=depth {
+            assert_eq!(
+                hashes[level],
+                hash_node(&hashes[level - 1], &hashes[level - 1]),
+                "Level {} should hash two copies of the level below",
+                level
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_hashes_zero_depth() {
+        assert_eq!(default_hashes(0), vec![hash_leaf(&[])]);
+    }
+
+    fn key(byte: u8) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        key[0] = byte;
+        key[31] = byte;
+        key
+    }
+
+    #[test]
+    fn test_empty_sparse_tree_root() {
+        let tree = SparseMerkleTree::new();
+        assert_eq!(tree.root(), default_hashes(SPARSE_DEPTH)[SPARSE_DEPTH]);
+    }
+
+    #[test]
+    fn test_sparse_inclusion() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(key(1), b"one");
+        tree.insert(key(2), b"two");
+        tree.insert(key(0x80), b"three");
+        let root = tree.root();
+
+        for (byte, value) in [(1, "one"), (2, "two"), (0x80, "three")] {
+            let proof = tree.prove(&key(byte));
+            assert_eq!(proof.value.as_deref(), Some(value.as_bytes()));
+            assert!(verify_sparse_proof(root, &proof));
+        }
+
+        let mut forged = tree.prove(&key(1));
+        forged.value = Some(b"two".to_vec());
+        assert!(!verify_sparse_proof(root, &forged));
+    }
+
+    #[test]
+    fn test_sparse_non_inclusion() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(key(1), b"one");
+        tree.insert(key(2), b"two");
+        let root = tree.root();
+
+        let proof = tree.prove(&key(3));
+        assert!(proof.value.is_none());
+        assert!(verify_sparse_proof(root, &proof));
+
+        // A present key cannot be proven absent
+        let mut forged = tree.prove(&key(1));
+        forged.value = None;
+        assert!(!verify_sparse_proof(root, &forged));
+    }
+
+    #[test]
+    fn test_sparse_update_changes_root() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(key(1), b"one");
+        tree.insert(key(2), b"two");
+        let root = tree.root();
+
+        tree.insert(key(1), b"updated");
+        assert_ne!(tree.root(), root);
+        assert_eq!(tree.get(&key(1)), Some("updated".as_bytes()));
+        assert!(!verify_sparse_proof(root, &tree.prove(&key(1))));
+        assert!(verify_sparse_proof(tree.root(), &tree.prove(&key(1))));
+
+        tree.insert(key(1), b"one");
+        assert_eq!(tree.root(), root);
+    }
+}
@@ -1,52 +1,1080 @@
 //! Enables verification of the generated Merkle proofs against the Merkle tree.
 
-use crate::hasher::hash_data_sequences;
-use crate::merkle_proof::MerkleProof;
+use crate::error::MerkleError;
+use crate::hasher::{Hasher, Sha256Hasher};
+#[cfg(feature = "std")]
+use crate::matrix::CellProof;
+use crate::merkle_proof::{
+    tree_height, CompactProof, ConsistencyProof, MerkleProof, MultiProof, PairingPolicy,
+    RangeProof, SelfContainedProof,
+};
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
 
-/// `Verifier` is responsible for verifying that a given `MerkleProof`
-/// matches a known Merkle tree root hash.
-pub struct Verifier {
+/// Receives callbacks while a `Verifier` checks a proof, e.g. to emit metrics.
+///
+/// Both methods default to doing nothing, so an observer only implements the events it needs.
+/// Observers are called through a shared reference and must therefore use interior
+/// mutability (such as atomics) to record state.
+pub trait VerifyObserver: Send + Sync {
+    /// Called after each combination step, with the level of the sibling hash
+    /// that was folded in (0 for the leaf level).
+    fn on_step(&self, _level: usize) {}
+
+    /// Called once the verification has finished, with its outcome.
+    fn on_result(&self, _valid: bool) {}
+}
+
+/// The reason a proof failed verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The proof folded into a root hash different from the expected one.
+    RootMismatch,
+    /// The proof has no authentication path, and its leaf hash is not the root hash.
+    EmptyPath,
+    /// The leaf index lies outside the tree, or the authentication path does not have the
+    /// length a leaf at that index has in a tree of the proof's size.
+    IndexPathLengthMismatch,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::RootMismatch => write!(f, "Computed root does not match the root hash"),
+            VerifyError::EmptyPath => write!(f, "Authentication path is empty"),
+            VerifyError::IndexPathLengthMismatch => {
+                write!(f, "Leaf index does not fit the authentication path length")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+/// A record of a single proof verification, suitable for persisting as an audit trail.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationReceipt {
+    /// The root hash the proof was checked against.
+    pub root: [u8; 32],
+    /// The leaf hash of the checked proof.
+    pub leaf_hash: [u8; 32],
+    /// The leaf index of the checked proof.
+    pub leaf_index: usize,
+    /// Whether the proof was valid.
+    pub verified: bool,
+    /// When the verification took place.
+    pub timestamp: SystemTime,
+}
+
+/// `MerkleVerifier` is responsible for verifying that a given `MerkleProof`
+/// matches a known Merkle tree root hash, hashing with `H`.
+pub struct MerkleVerifier<H: Hasher> {
     /// The root hash of the Merkle tree against which proofs will be verified.
     root_hash: [u8; 32],
+    /// Optional observer notified during verification.
+    observer: Option<Box<dyn VerifyObserver>>,
+    /// How the tree combined the last node of odd-sized levels.
+    pairing: PairingPolicy,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+/// A `MerkleVerifier` hashing with SHA256.
+pub type Verifier = MerkleVerifier<Sha256Hasher>;
+
+impl<H: Hasher> MerkleVerifier<H> {
+    pub fn new(root_hash: [u8; 32]) -> Self {
+        MerkleVerifier {
+            root_hash,
+            observer: None,
+            pairing: PairingPolicy::Promote,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Creates a Verifier for a tree built with the given pairing policy, see
+    /// `ProverBuilder::pairing`.
+    ///
+    /// The policy applies to the verification of single-leaf proofs, i.e. `verify_proof` and
+    /// the methods built on it. Multiproofs, range proofs and consistency proofs are only
+    /// generated for trees that promote.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_hash` - The root hash against which proofs will be verified.
+    /// * `pairing` - The pairing policy the tree was built with.
+    pub fn with_pairing(root_hash: [u8; 32], pairing: PairingPolicy) -> Self {
+        MerkleVerifier {
+            pairing,
+            ..Self::new(root_hash)
+        }
+    }
+
+    /// Creates a Verifier from a root hash given as a 64-digit hex string, as printed in logs.
+    ///
+    /// Upper- and lowercase digits are accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `hex` - The hex encoded root hash.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the created Verifier, or a `MerkleError` if the string does not
+    /// have 64 characters or holds a character that is not a hex digit.
+    pub fn from_hex(hex: &str) -> Result<Self, MerkleError> {
+        if hex.len() != 64 {
+            return Err(MerkleError::InvalidHexLength { len: hex.len() });
+        }
+
+        let digit = |index: usize| {
+            char::from(hex.as_bytes()[index])
+                .to_digit(16)
+                .map(|value| value as u8)
+                .ok_or(MerkleError::InvalidHexDigit { index })
+        };
+        let mut root_hash = [0u8; 32];
+        for (i, byte) in root_hash.iter_mut().enumerate() {
+            *byte = (digit(2 * i)? << 4) | digit(2 * i + 1)?;
+        }
+        Ok(Self::new(root_hash))
+    }
+
+    /// Creates a Verifier that reports each verification step and result to `observer`.
+    ///
+    /// The observer does not influence the outcome of the verification.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_hash` - The root hash against which proofs will be verified.
+    /// * `observer` - The observer receiving the callbacks.
+    pub fn with_observer(root_hash: [u8; 32], observer: impl VerifyObserver + 'static) -> Self {
+        MerkleVerifier {
+            root_hash,
+            observer: Some(Box::new(observer)),
+            pairing: PairingPolicy::Promote,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Computes the Merkle tree root hash using the provided `proof` and checks
+    /// if it matches the `Verifier`'s known root hash.
+    ///
+    /// The leaf index and the tree size together select the hashing order, and which levels
+    /// promote a node without a sibling. A proof whose leaf index is not below its tree size,
+    /// or whose path is not exactly as long as that leaf's path in a tree of that size, is
+    /// rejected before folding.
+    ///
+    /// In a tree of a single leaf, the leaf hash is the root hash. The proof of that leaf has
+    /// an empty path and is valid exactly when its leaf hash equals the root hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid, otherwise returns `false`.
+    pub fn verify_proof(&self, proof: &MerkleProof) -> bool {
+        self.verify_proof_detailed(proof).is_ok()
+    }
+
+    /// Verifies a proof like `verify_proof`, reporting why verification failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the proof is valid, otherwise the `VerifyError` describing the failure.
+    pub fn verify_proof_detailed(&self, proof: &MerkleProof) -> Result<(), VerifyError> {
+        self.verify_with(proof, |_| {})
+    }
+
+    /// Verifies `proof`, notifying the observer and calling `on_step` after each fold step.
+    fn verify_with(
+        &self,
+        proof: &MerkleProof,
+        mut on_step: impl FnMut(usize),
+    ) -> Result<(), VerifyError> {
+        let result = self.check_proof(proof, |height| {
+            if let Some(observer) = &self.observer {
+                observer.on_step(height);
+            }
+            on_step(height);
+        });
+
+        if let Some(observer) = &self.observer {
+            observer.on_result(result.is_ok());
+        }
+        result
+    }
+
+    /// Checks the shape of `proof` and then folds it into a root to compare.
+    fn check_proof(
+        &self,
+        proof: &MerkleProof,
+        on_step: impl FnMut(usize),
+    ) -> Result<(), VerifyError> {
+        // A single leaf is the root itself, so there is nothing to fold
+        if proof.tree_size == 1 {
+            if proof.leaf_index != 0 || !proof.authentication_path.is_empty() {
+                return Err(VerifyError::IndexPathLengthMismatch);
+            }
+            if !hashes_equal(&proof.leaf_hash, &self.root_hash) {
+                return Err(VerifyError::EmptyPath);
+            }
+            return Ok(());
+        }
+
+        if proof.authentication_path.is_empty() && !hashes_equal(&proof.leaf_hash, &self.root_hash)
+        {
+            return Err(VerifyError::EmptyPath);
+        }
+        // The leaf index and tree size determine exactly how long the path must be
+        if !proof.fits_tree_size_with(self.pairing) {
+            return Err(VerifyError::IndexPathLengthMismatch);
+        }
+
+        let root = fold_path_with::<H>(proof, self.pairing, on_step);
+        if root.is_some_and(|root| hashes_equal(&root, &self.root_hash)) {
+            Ok(())
+        } else {
+            Err(VerifyError::RootMismatch)
+        }
+    }
+
+    /// Verifies a proof only if it is for the leaf at `expected_index`.
+    ///
+    /// This stops a prover from answering a query for one position with a valid proof for
+    /// another.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_index` - The index of the leaf that was asked for.
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is for `expected_index` and is valid, otherwise returns
+    /// `false`.
+    pub fn verify_proof_at(&self, expected_index: usize, proof: &MerkleProof) -> bool {
+        proof.leaf_index == expected_index && self.verify_proof(proof)
+    }
+
+    /// Verifies a proof against several acceptable root hashes, e.g. the last few known
+    /// checkpoints, instead of the root hash of this Verifier.
+    ///
+    /// The path is folded only once, and the result is compared with each candidate.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    /// * `roots` - The acceptable root hashes.
+    ///
+    /// # Returns
+    ///
+    /// The index of the first root in `roots` the proof is valid for, or `None` if it is valid
+    /// for none of them.
+    pub fn verify_against_any(&self, proof: &MerkleProof, roots: &[[u8; 32]]) -> Option<usize> {
+        if !proof.fits_tree_size_with(self.pairing) {
+            return None;
+        }
+        let root = fold_path_with::<H>(proof, self.pairing, |_| {})?;
+        roots
+            .iter()
+            .position(|candidate| hashes_equal(&root, candidate))
+    }
+
+    /// Verifies a batch of proofs against the root hash, stopping at the first invalid one.
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - The `MerkleProof`s to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if every proof is valid, otherwise returns `false`. An empty batch is
+    /// valid.
+    pub fn verify_batch(&self, proofs: &[MerkleProof]) -> bool {
+        proofs.iter().all(|proof| self.verify_proof(proof))
+    }
+
+    /// Verifies a batch of proofs like `verify_batch`, verifying repeated proofs only once.
+    ///
+    /// Each proof is identified by the hash of its binary encoding, see
+    /// `MerkleProof::to_bytes`, so a proof equal to one already verified is skipped. This is
+    /// faster than `verify_batch` when the batch holds many copies of a few proofs.
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - The `MerkleProof`s to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if every proof is valid, otherwise returns `false`. An empty batch is
+    /// valid.
+    pub fn verify_batch_dedup(&self, proofs: &[MerkleProof]) -> bool {
+        let mut verified = BTreeSet::new();
+        proofs.iter().all(|proof| {
+            let key = H::hash(&[&proof.to_bytes()]);
+            verified.contains(&key) || (self.verify_proof(proof) && verified.insert(key))
+        })
+    }
+
+    /// Verifies a batch of proofs like `verify_batch`, spreading the proofs over the rayon
+    /// thread pool.
+    ///
+    /// The remaining proofs are skipped once an invalid proof is found, but proofs already
+    /// being checked on other threads are finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - The `MerkleProof`s to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if every proof is valid, otherwise returns `false`.
+    #[cfg(feature = "std")]
+    pub fn verify_batch_parallel(&self, proofs: &[MerkleProof]) -> bool {
+        proofs.par_iter().all(|proof| self.verify_proof(proof))
+    }
+
+    /// Verifies a proof only if its leaf index satisfies `pred`.
+    ///
+    /// This lets schemes that assign meaning to positions, e.g. even indices for data and
+    /// odd indices for parity, reject proofs targeting the wrong kind of leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    /// * `pred` - The constraint the leaf index of the proof must satisfy.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the leaf index satisfies `pred` and the proof is valid,
+    /// otherwise returns `false`.
+    pub fn verify_with_index_predicate(
+        &self,
+        proof: &MerkleProof,
+        pred: impl Fn(usize) -> bool,
+    ) -> bool {
+        pred(proof.leaf_index) && self.verify_proof(proof)
+    }
+
+    /// Verifies a proof like `verify_proof` and also counts the hash operations performed.
+    ///
+    /// Each authentication path entry costs exactly one hash, so for any proof that gets
+    /// folded the count equals the path length and can be used to estimate verification
+    /// cost deterministically.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the verification result and the number of hash operations performed.
+    pub fn verify_counting(&self, proof: &MerkleProof) -> (bool, usize) {
+        let mut hash_count = 0;
+        let valid = self.verify_with(proof, |_| hash_count += 1).is_ok();
+        (valid, hash_count)
+    }
+
+    /// Verifies a Merkle proof and records what was checked in a `VerificationReceipt`.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// A receipt holding the root hash, the leaf hash and index of the proof, the
+    /// verification result and the time of the verification.
+    #[cfg(feature = "std")]
+    pub fn verify_with_receipt(&self, proof: &MerkleProof) -> VerificationReceipt {
+        VerificationReceipt {
+            root: self.root_hash,
+            leaf_hash: proof.leaf_hash,
+            leaf_index: proof.leaf_index,
+            verified: self.verify_proof(proof),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Checks that a proof is for the given leaf data, and that the proof is valid.
+    ///
+    /// The leaf hash is recomputed from `data` first, so a valid proof for a different leaf
+    /// is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The leaf data the proof should be for.
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if `data` matches the proof's leaf hash and the proof is valid,
+    /// otherwise returns `false`.
+    pub fn verify_proof_for_data(&self, data: &str, proof: &MerkleProof) -> bool {
+        H::hash_leaf(data.as_bytes()) == proof.leaf_hash && self.verify_proof(proof)
+    }
+
+    /// Verifies a compact proof for the given leaf data.
+    ///
+    /// The leaf hash is computed from `data` and then checked exactly as `verify_proof`
+    /// checks a full proof. Proofs from salted trees cannot be verified this way.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The leaf data the proof is for.
+    /// * `proof` - The `CompactProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid for `data`, otherwise returns `false`.
+    pub fn verify_compact(&self, data: &str, proof: &CompactProof) -> bool {
+        self.verify_proof(&MerkleProof {
+            leaf_index: proof.leaf_index,
+            tree_size: proof.tree_size,
+            leaf_hash: H::hash_leaf(data.as_bytes()),
+            authentication_path: proof.authentication_path.clone(),
+            leaf_data: None,
+        })
+    }
+
+    /// Checks that a proof is for the given leaf data in a tree whose leaves were salted with
+    /// `salt`, see `ProverBuilder::salt`, and that the proof is valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `salt` - The salt of the tree.
+    /// * `data` - The leaf data the proof should be for.
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the salted hash of `data` matches the proof's leaf hash and the proof
+    /// is valid, otherwise returns `false`.
+    pub fn verify_proof_for_salted_data(
+        &self,
+        salt: &[u8],
+        data: &str,
+        proof: &MerkleProof,
+    ) -> bool {
+        H::hash_salted_leaf(salt, data.as_bytes()) == proof.leaf_hash && self.verify_proof(proof)
+    }
+
+    /// Checks that the leaf at `index` does not hold `value`.
+    ///
+    /// The proof must be a valid inclusion proof for the leaf at `index`, and that leaf's hash
+    /// must differ from the hash of `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the leaf the claim is about.
+    /// * `value` - The value the leaf is claimed not to hold.
+    /// * `proof` - The `MerkleProof` of the actual leaf at `index`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid for `index` and its leaf is not `value`,
+    /// otherwise returns `false`.
+    pub fn verify_value_not_at(&self, index: usize, value: &str, proof: &MerkleProof) -> bool {
+        proof.leaf_index == index
+            && proof.leaf_hash != H::hash_leaf(value.as_bytes())
+            && self.verify_proof(proof)
+    }
+
+    /// Checks that encoded leaf data decodes to the leaf a proof is for, and that the proof
+    /// is valid.
+    ///
+    /// This verifies proofs from a Prover created with `with_decode`, given the same
+    /// `decode` function.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The encoded leaf data.
+    /// * `decode` - Decodes the leaf into the bytes the tree commits to.
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the decoded data matches the proof's leaf hash and the proof is
+    /// valid, otherwise returns `false`.
+    pub fn verify_data_decoded(
+        &self,
+        data: &[u8],
+        decode: impl Fn(&[u8]) -> Vec<u8>,
+        proof: &MerkleProof,
+    ) -> bool {
+        H::hash_leaf(&decode(data)) == proof.leaf_hash && self.verify_proof(proof)
+    }
+
+    /// Checks a proof from a Prover created with `new_sequenced` for the expected sequence number.
+    ///
+    /// The leaf hash is recomputed from `expected_seq` and `data`, so a proof generated under
+    /// any other sequence number is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The leaf data the proof should be for.
+    /// * `expected_seq` - The sequence number the proof must be bound to.
+    /// * `proof` - The `MerkleProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is for `data` under `expected_seq` and is valid,
+    /// otherwise returns `false`.
+    pub fn verify_sequenced(&self, data: &str, expected_seq: u64, proof: &MerkleProof) -> bool {
+        let leaf = [&expected_seq.to_le_bytes()[..], data.as_bytes()].concat();
+        H::hash_leaf(&leaf) == proof.leaf_hash && self.verify_proof(proof)
+    }
+
+    /// Checks a proof against the root hash only if the root is bound to `meta` by `commitment`.
+    ///
+    /// The commitment must be the hash of the root hash followed by `meta`, as computed by
+    /// `Prover::committed_root_with_meta`, so a proof is rejected when the metadata was swapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to be verified.
+    /// * `meta` - The metadata published alongside the root.
+    /// * `commitment` - The published commitment over the root hash and the metadata.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the commitment matches the root hash and `meta` and the proof is
+    /// valid, otherwise returns `false`.
+    pub fn verify_proof_with_meta(
+        &self,
+        proof: &MerkleProof,
+        meta: &[u8],
+        commitment: [u8; 32],
+    ) -> bool {
+        H::hash(&[&self.root_hash, meta]) == commitment && self.verify_proof(proof)
+    }
+
+    /// Checks a proof carrying its own leaf data, as produced by `Prover::get_proof_inline`.
+    ///
+    /// The leaf hash is recomputed from the embedded data before the proof is verified,
+    /// so tampering with either the data or the path is detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` with embedded leaf data to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof carries data matching its leaf hash and is valid,
+    /// otherwise returns `false`.
+    pub fn verify_inline_proof(&self, proof: &MerkleProof) -> bool {
+        match &proof.leaf_data {
+            Some(data) => H::hash_leaf(data) == proof.leaf_hash && self.verify_proof(proof),
+            None => false,
+        }
+    }
+
+    /// Checks that all leaves of a `MultiProof` are included in the tree.
+    ///
+    /// The known nodes are combined level by level, taking a hash from the proof's sibling
+    /// hashes whenever the sibling of a known node is not known itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MultiProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid, otherwise returns `false`.
+    pub fn verify_multiproof(&self, proof: &MultiProof) -> bool {
+        if proof.leaf_indices.is_empty() || proof.leaf_indices.len() != proof.leaf_hashes.len() {
+            return false;
+        }
+        // Indices must be strictly increasing and inside the tree
+        if proof.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1])
+            || *proof.leaf_indices.last().unwrap() >= proof.tree_size
+        {
+            return false;
+        }
+
+        let mut known: Vec<(usize, [u8; 32])> = proof
+            .leaf_indices
+            .iter()
+            .copied()
+            .zip(proof.leaf_hashes.iter().copied())
+            .collect();
+        let mut siblings = proof.sibling_hashes.iter();
+        let mut width = proof.tree_size;
+
+        for _ in 0..tree_height(proof.tree_size) {
+            let mut next_known = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let (position, hash) = known[i];
+                let parent_hash = match known.get(i + 1) {
+                    Some(&(next_position, next_hash))
+                        if position.is_multiple_of(2) && next_position == position + 1 =>
+                    {
+                        i += 1;
+                        H::hash_node(&hash, &next_hash)
+                    }
+                    // The last node of an odd-sized level is promoted unchanged
+                    _ if position ^ 1 >= width => hash,
+                    _ => {
+                        let Some(sibling) = siblings.next() else {
+                            return false;
+                        };
+                        if position % 2 == 1 {
+                            H::hash_node(sibling, &hash)
+                        } else {
+                            H::hash_node(&hash, sibling)
+                        }
+                    }
+                };
+                next_known.push((position / 2, parent_hash));
+                i += 1;
+            }
+            known = next_known;
+            width = width.div_ceil(2);
+        }
+
+        // Every sibling must be used, and everything must have folded into the root
+        siblings.next().is_none() && known == [(0, self.root_hash)]
+    }
+
+    /// Checks that all leaves of a `RangeProof` are included in the tree.
+    ///
+    /// The nodes covering the range are combined level by level, extending the range with a
+    /// boundary sibling from the proof wherever it starts or ends in the middle of a pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `RangeProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid, otherwise returns `false`.
+    pub fn verify_range(&self, proof: &RangeProof) -> bool {
+        let Some(end) = proof.start.checked_add(proof.leaf_hashes.len()) else {
+            return false;
+        };
+        if proof.leaf_hashes.is_empty() || end > proof.tree_size {
+            return false;
+        }
+
+        let mut nodes = proof.leaf_hashes.clone();
+        let mut siblings = proof.sibling_hashes.iter();
+        let (mut low, mut high, mut width) = (proof.start, end, proof.tree_size);
+
+        for _ in 0..tree_height(proof.tree_size) {
+            if low % 2 == 1 {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                nodes.insert(0, *sibling);
+                low -= 1;
+            }
+            // The last node of an odd-sized level is promoted unchanged
+            if high % 2 == 1 && high < width {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                nodes.push(*sibling);
+                high += 1;
+            }
+
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => H::hash_node(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            low /= 2;
+            high = high.div_ceil(2);
+            width = width.div_ceil(2);
+        }
+
+        // Every sibling must be used, and everything must have folded into the root
+        siblings.next().is_none() && nodes == [self.root_hash]
+    }
+
+    /// Checks that the tree with root `new_root` extends the tree with root `old_root`.
+    ///
+    /// Both roots are recomputed from the proof with the algorithm of RFC 9162, section
+    /// 2.1.4.2. The roots are passed explicitly, so the Verifier's own root hash is not
+    /// involved.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_root` - The root hash of the old tree, of `proof.old_size` leaves.
+    /// * `new_root` - The root hash of the new tree, of `proof.new_size` leaves.
+    /// * `proof` - The `ConsistencyProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof shows that the new tree extends the old one, otherwise
+    /// returns `false`.
+    pub fn verify_consistency(
+        &self,
+        old_root: [u8; 32],
+        new_root: [u8; 32],
+        proof: &ConsistencyProof,
+    ) -> bool {
+        let (old_size, new_size) = (proof.old_size, proof.new_size);
+        if old_size == 0 || old_size > new_size {
+            return false;
+        }
+        if old_size == new_size {
+            return proof.hashes.is_empty() && old_root == new_root;
+        }
+
+        // The old root is the first hash if the old tree is a complete subtree of the new one
+        let mut hashes = proof.hashes.iter();
+        let first_hash = if old_size.is_power_of_two() {
+            old_root
+        } else {
+            match hashes.next() {
+                Some(hash) => *hash,
+                None => return false,
+            }
+        };
+
+        let (mut old_node, mut new_node) = (old_size - 1, new_size - 1);
+        while old_node & 1 == 1 {
+            old_node >>= 1;
+            new_node >>= 1;
+        }
+
+        let (mut old_hash, mut new_hash) = (first_hash, first_hash);
+        for hash in hashes {
+            if new_node == 0 {
+                return false;
+            }
+            if old_node & 1 == 1 || old_node == new_node {
+                old_hash = H::hash_node(hash, &old_hash);
+                new_hash = H::hash_node(hash, &new_hash);
+                while old_node & 1 == 0 && old_node != 0 {
+                    old_node >>= 1;
+                    new_node >>= 1;
+                }
+            } else {
+                new_hash = H::hash_node(&new_hash, hash);
+            }
+            old_node >>= 1;
+            new_node >>= 1;
+        }
+
+        new_node == 0 && old_hash == old_root && new_hash == new_root
+    }
+
+    /// Checks a cell proof against the root of a `MatrixCommitment`.
+    ///
+    /// The cell proof is folded into its row root, which must be the leaf of the row proof,
+    /// and the row proof is then folded into the matrix root.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `CellProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the cell is included in the committed matrix, otherwise returns `false`.
+    #[cfg(feature = "std")]
+    pub fn verify_cell_proof(&self, proof: &CellProof) -> bool {
+        if !proof.cell_proof.fits_tree_size() {
+            return false;
+        }
+        let row_root = fold_path::<H>(&proof.cell_proof, |_| {});
+        H::hash_leaf(&row_root) == proof.row_proof.leaf_hash && self.verify_proof(&proof.row_proof)
+    }
+
+    /// Checks that a leaf is included in the tree with this verifier's root hash, and that
+    /// this root is one of the tree roots committed to by `super_root`.
+    ///
+    /// The super-root is the root of a tree whose leaves are the roots of the trees in the
+    /// forest, e.g. built with `Prover::from_bytes` over them, so the full path folds from the
+    /// leaf up to the super-root. The super tree must use this verifier's pairing policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `super_root` - The root hash of the tree over the forest's tree roots.
+    /// * `forest_proof` - The `MerkleProof` of this verifier's root hash in the super tree.
+    /// * `tree_proof` - The `MerkleProof` of the leaf in its tree.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the leaf is included in its tree and the tree is included in the
+    /// forest, otherwise returns `false`.
+    pub fn verify_in_forest(
+        &self,
+        super_root: [u8; 32],
+        forest_proof: &MerkleProof,
+        tree_proof: &MerkleProof,
+    ) -> bool {
+        H::hash_leaf(&self.root_hash) == forest_proof.leaf_hash
+            && forest_proof.fits_tree_size_with(self.pairing)
+            && fold_path_with::<H>(forest_proof, self.pairing, |_| {})
+                .is_some_and(|root| hashes_equal(&root, &super_root))
+            && self.verify_proof(tree_proof)
+    }
+
+    /// Checks that the proof of a `SelfContainedProof` leads to the root hash embedded in it.
+    ///
+    /// This only shows that the bundle is internally consistent, not that its root is
+    /// authentic, since anyone can bundle a proof with the root of a tree they built
+    /// themselves. In real use the embedded root must still be compared with a root from a
+    /// trusted source, or the proof verified with a Verifier created from that root instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `SelfContainedProof` to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is valid for the embedded root, otherwise returns `false`.
+    pub fn verify_self_contained(proof: &SelfContainedProof) -> bool {
+        Self::new(proof.root).verify_proof(&proof.proof)
+    }
+
+    /// Folds the authentication path of a proof and returns the root hash it leads to.
+    ///
+    /// This is the hash `verify_proof` compares with the root hash, which makes it useful for
+    /// diagnosing a failed verification. The shape of the proof is not checked, so a path of
+    /// the wrong length still yields a hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `MerkleProof` to fold.
+    ///
+    /// # Returns
+    ///
+    /// The root hash recomputed from the proof.
+    pub fn compute_root(proof: &MerkleProof) -> [u8; 32] {
+        fold_path::<H>(proof, |_| {})
+    }
+
+    /// Combines a compact range into the root hash of a tree built over the first `k` leaves.
+    ///
+    /// The compact range must hold the perfect subtree roots covering `[0, k)` ordered from left
+    /// to right, as produced by `Prover::compact_range`. The subtrees are combined from right to
+    /// left: a lower right subtree is promoted to the height of its left neighbour unchanged,
+    /// mirroring how the tree promotes the last node of odd-sized levels.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The number of leaves covered by the compact range.
+    /// * `range` - The subtree roots, one for each set bit of `k`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the root hash at size `k`, or a `MerkleError` if the range does not
+    /// match `k`.
+    pub fn compact_range_root(k: usize, range: &[[u8; 32]]) -> Result<[u8; 32], MerkleError> {
+        if k == 0 {
+            return Err(MerkleError::EmptyPrefix);
+        }
+        if range.len() != k.count_ones() as usize {
+            return Err(MerkleError::CompactRangeMismatch {
+                len: range.len(),
+                prefix: k,
+            });
+        }
+
+        let mut hashes = range.iter().rev();
+        let last_hash = *hashes.next().unwrap();
+
+        Ok(hashes.fold(last_hash, |current_hash, hash| {
+            H::hash_node(hash, &current_hash)
+        }))
+    }
 }
 
-impl Verifier {
-    pub fn new(root_hash: [u8; 32]) -> Self {
-        Verifier { root_hash }
+/// Verifies an authentication path whose sibling hashes arrive one at a time, e.g. over the
+/// network, without holding the whole `MerkleProof` in memory.
+///
+/// The siblings must be pushed from the leaf up to the root, which is the reverse of the
+/// order in `MerkleProof::authentication_path`. The same steps as in `verify_proof` are
+/// applied, so levels where the node of the leaf is promoted without a sibling are skipped.
+pub struct MerkleStreamingVerifier<H: Hasher> {
+    /// The hash of the node of the leaf at the current level.
+    current_hash: [u8; 32],
+    /// The position of the node of the leaf within the current level.
+    position: usize,
+    /// The number of nodes at the current level.
+    width: usize,
+    /// Whether more siblings were pushed than the path has levels.
+    overflowed: bool,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+/// A `MerkleStreamingVerifier` hashing with SHA256.
+pub type StreamingVerifier = MerkleStreamingVerifier<Sha256Hasher>;
+
+impl<H: Hasher> MerkleStreamingVerifier<H> {
+    /// Creates a StreamingVerifier for the path of one leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf_hash` - The hash of the leaf.
+    /// * `leaf_index` - The index of the leaf in the tree.
+    /// * `tree_size` - The number of leaves in the tree, which determines at which levels
+    ///   the node of the leaf is promoted.
+    pub fn new(leaf_hash: [u8; 32], leaf_index: usize, tree_size: usize) -> Self {
+        let mut verifier = MerkleStreamingVerifier {
+            current_hash: leaf_hash,
+            position: leaf_index,
+            width: tree_size,
+            overflowed: leaf_index >= tree_size,
+            _hasher: PhantomData,
+        };
+        verifier.skip_promoted_levels();
+        verifier
     }
-    /// Computes the Merkle tree root hash using the provided `proof` and checks
-    /// if it matches the `Verifier`'s known root hash.
+
+    /// Folds the next sibling hash into the node of the leaf, moving one level up.
     ///
     /// # Arguments
     ///
-    /// * `proof` - The `MerkleProof` to be verified.
+    /// * `hash` - The sibling hash at the current level.
+    pub fn push_sibling(&mut self, hash: [u8; 32]) {
+        if self.overflowed || self.width <= 1 {
+            self.overflowed = true;
+            return;
+        }
+
+        self.current_hash = if self.position & 1 != 0 {
+            H::hash_node(&hash, &self.current_hash)
+        } else {
+            H::hash_node(&self.current_hash, &hash)
+        };
+        self.position >>= 1;
+        self.width = self.width.div_ceil(2);
+        self.skip_promoted_levels();
+    }
+
+    /// Checks that the pushed siblings lead from the leaf to `expected_root`.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_root` - The root hash of the tree.
     ///
     /// # Returns
     ///
-    /// Returns `true` if the proof is valid, otherwise returns `false`.
-    pub fn verify_proof(&self, proof: &MerkleProof) -> bool {
-        let mut height: usize = 0;
-        let mut current_hash = proof.leaf_hash.clone();
+    /// Returns `true` if exactly the siblings of the leaf's path were pushed and they lead to
+    /// `expected_root`, otherwise returns `false`.
+    pub fn finish(self, expected_root: [u8; 32]) -> bool {
+        !self.overflowed && self.width == 1 && hashes_equal(&self.current_hash, &expected_root)
+    }
 
-        for hash in proof.authentication_path.iter().rev() {
-            let direction = (1 << height) & proof.leaf_index;
+    /// Moves up past the levels where the node of the leaf has no sibling and is promoted.
+    fn skip_promoted_levels(&mut self) {
+        while self.width > 1 && self.position ^ 1 >= self.width {
+            self.position >>= 1;
+            self.width = self.width.div_ceil(2);
+        }
+    }
+}
+
+/// Compares two hashes in constant time.
+///
+/// Every byte is compared regardless of where the first difference is, so the time taken
+/// does not reveal how many leading bytes of a computed root match the expected one.
+pub(crate) fn hashes_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |difference, (x, y)| difference | (x ^ y));
+    // Keep the compiler from turning the accumulation into an early-exit comparison
+    core::hint::black_box(difference) == 0
+}
+
+/// Folds the authentication path of `proof` into the root hash it commits to.
+///
+/// This is the single place where proofs are recomputed, so every verification method
+/// agrees on the result. `on_step` is called after each combination with the level of
+/// the sibling hash that was folded in. Levels where the node of the leaf is promoted
+/// without a sibling are skipped. The path is assumed to fit the tree size, see
+/// `MerkleProof::fits_tree_size`; excess path entries are ignored.
+pub(crate) fn fold_path<H: Hasher>(proof: &MerkleProof, on_step: impl FnMut(usize)) -> [u8; 32] {
+    fold_path_with::<H>(proof, PairingPolicy::Promote, on_step)
+        .expect("Promoting trees have no stand-in siblings")
+}
+
+/// Folds the authentication path like `fold_path`, for a tree built with `pairing`.
+///
+/// Unless the tree promotes, a node without a sibling was paired with a stand-in that is part
+/// of the path, so every level consumes a path entry. Returns `None` if a stand-in in the path
+/// is not the one `pairing` prescribes.
+pub(crate) fn fold_path_with<H: Hasher>(
+    proof: &MerkleProof,
+    pairing: PairingPolicy,
+    mut on_step: impl FnMut(usize),
+) -> Option<[u8; 32]> {
+    let mut current_hash = proof.leaf_hash;
+    let mut path = proof.authentication_path.iter().rev();
+    let (mut position, mut width, mut height) = (proof.leaf_index, proof.tree_size, 0);
 
-            let combined_hash = if direction != 0 {
-                hash_data_sequences(&[hash, &current_hash])
+    while width > 1 {
+        if position ^ 1 < width || pairing != PairingPolicy::Promote {
+            let Some(hash) = path.next() else {
+                break;
+            };
+            if position ^ 1 >= width && pairing.lone_sibling(&current_hash) != Some(*hash) {
+                return None;
+            }
+            current_hash = if position & 1 != 0 {
+                H::hash_node(hash, &current_hash)
             } else {
-                hash_data_sequences(&[&current_hash, hash])
+                H::hash_node(&current_hash, hash)
             };
-            current_hash = combined_hash;
-            height += 1;
+            on_step(height);
         }
-
-        current_hash == self.root_hash
+        position >>= 1;
+        width = width.div_ceil(2);
+        height += 1;
     }
+
+    Some(current_hash)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use crate::hasher::{hash_leaf, hash_node};
+    use crate::Prover;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Observer counting the callbacks it receives.
+    #[derive(Default)]
+    struct CountingObserver {
+        steps: AtomicUsize,
+        next_level: AtomicUsize,
+        result: AtomicBool,
+    }
+
+    impl VerifyObserver for Arc<CountingObserver> {
+        fn on_step(&self, level: usize) {
+            // Levels must be reported from the leaf upwards, skipping promoted levels
+            assert!(self.next_level.swap(level + 1, Ordering::SeqCst) <= level);
+            self.steps.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_result(&self, valid: bool) {
+            self.result.store(valid, Ordering::SeqCst);
+        }
+    }
 
     #[test]
     fn test_verifier_new() {
@@ -55,4 +1083,816 @@ mod tests {
         let verifier = Verifier::new(valid_root_hash);
         assert_eq!(verifier.root_hash, valid_root_hash);
     }
+
+    #[test]
+    fn test_observer_counts_steps() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let root_hash = prover.get_root_hash().unwrap();
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+
+            let observer = Arc::new(CountingObserver::default());
+            let verifier = Verifier::with_observer(root_hash, observer.clone());
+
+            assert!(verifier.verify_proof(&proof));
+            assert_eq!(
+                observer.steps.load(Ordering::SeqCst),
+                proof.authentication_path.len(),
+                "on_step should fire once per path entry"
+            );
+            assert!(observer.result.load(Ordering::SeqCst));
+        }
+    }
+
+    #[test]
+    fn test_verify_counting() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            let (valid, hash_count) = verifier.verify_counting(&proof);
+
+            assert!(valid);
+            assert_eq!(hash_count, proof.authentication_path.len());
+            assert_eq!(valid, verifier.verify_proof(&proof));
+        }
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let data = vec!["data1", "data2", "data3"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let hex: String = prover.root().iter().map(|b| format!("{:02x}", b)).collect();
+
+        let verifier = Verifier::from_hex(&hex).expect("Failed to parse hex root");
+        assert!(verifier.verify_proof(&prover.get_proof(1).unwrap()));
+        let verifier = Verifier::from_hex(&hex.to_uppercase()).expect("Failed to parse hex root");
+        assert!(verifier.verify_proof(&prover.get_proof(1).unwrap()));
+
+        assert_eq!(
+            Verifier::from_hex(&hex[..63]).err(),
+            Some(MerkleError::InvalidHexLength { len: 63 })
+        );
+        let mut invalid = hex.clone();
+        invalid.replace_range(10..11, "g");
+        assert_eq!(
+            Verifier::from_hex(&invalid).err(),
+            Some(MerkleError::InvalidHexDigit { index: 10 })
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_at() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        // A valid proof for the asked index is accepted
+        let proof = prover.get_proof(3).unwrap();
+        assert!(verifier.verify_proof_at(3, &proof));
+
+        // A valid proof for another index is rejected
+        let other_proof = prover.get_proof(5).unwrap();
+        assert!(verifier.verify_proof(&other_proof));
+        assert!(!verifier.verify_proof_at(3, &other_proof));
+
+        // The expected index does not make an invalid proof valid
+        let wrong_verifier = Verifier::new([0u8; 32]);
+        assert!(!wrong_verifier.verify_proof_at(3, &proof));
+    }
+
+    #[test]
+    fn test_hashes_equal() {
+        let hash = hash_leaf(b"data");
+        assert!(hashes_equal(&hash, &hash));
+        assert!(hashes_equal(&[0u8; 32], &[0u8; 32]));
+
+        // A difference in the first, a middle, or the last byte is detected
+        for position in [0, 15, 31] {
+            let mut other = hash;
+            other[position] ^= 0x80;
+            assert!(!hashes_equal(&hash, &other));
+            assert!(!hashes_equal(&other, &hash));
+        }
+        assert!(!hashes_equal(&hash, &hash_leaf(b"other")));
+
+        // Verification still accepts matching roots and rejects mismatching ones
+        let prover = Prover::new(&["data0", "data1", "data2"], 1).unwrap();
+        let proof = prover.get_proof(1).unwrap();
+        assert!(Verifier::new(prover.root()).verify_proof(&proof));
+        let mut wrong_root = prover.root();
+        wrong_root[31] ^= 1;
+        assert_eq!(
+            Verifier::new(wrong_root).verify_proof_detailed(&proof),
+            Err(VerifyError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_for_salted_data() {
+        let data = vec!["yes", "no", "yes", "no", "no"];
+        let prover = Prover::builder()
+            .data(&data)
+            .salt(b"salt")
+            .build()
+            .expect("Failed to create prover");
+        let verifier = Verifier::new(prover.root());
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert!(verifier.verify_proof_for_salted_data(b"salt", leaf, &proof));
+
+            // A wrong salt, a missing salt, or other data is rejected
+            assert!(!verifier.verify_proof_for_salted_data(b"pepper", leaf, &proof));
+            assert!(!verifier.verify_proof_for_salted_data(b"", leaf, &proof));
+            assert!(!verifier.verify_proof_for_data(leaf, &proof));
+            assert!(!verifier.verify_proof_for_salted_data(b"salt", "maybe", &proof));
+        }
+    }
+
+    #[test]
+    fn test_verify_against_any() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+        let older = Prover::new(&data[..3], 1).unwrap();
+        let current = Prover::new(&data, 1).unwrap();
+        let other = Prover::new(&["other0", "other1"], 1).unwrap();
+        let roots = [older.root(), current.root(), other.root()];
+        let verifier = Verifier::new(current.root());
+
+        let proof = current.get_proof(3).unwrap();
+        assert_eq!(verifier.verify_against_any(&proof, &roots), Some(1));
+        assert_eq!(
+            verifier.verify_against_any(&proof, &[older.root(), other.root()]),
+            None
+        );
+        assert_eq!(verifier.verify_against_any(&proof, &[]), None);
+
+        // A proof of the wrong shape is rejected whatever root it folds to
+        let mut truncated = proof.clone();
+        truncated.authentication_path.remove(0);
+        let folded = Verifier::compute_root(&truncated);
+        assert_eq!(verifier.verify_against_any(&truncated, &[folded]), None);
+    }
+
+    #[test]
+    fn test_directions() {
+        for size in 1..=9 {
+            let data: Vec<String> = (0..size).map(|i| format!("data{}", i)).collect();
+            let leaves: Vec<&str> = data.iter().map(String::as_str).collect();
+            let prover = Prover::new(&leaves, 1).expect("Failed to create prover");
+            let verifier = Verifier::new(prover.root());
+
+            for leaf_index in 0..size {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                let directions = proof.directions();
+                assert_eq!(directions.len(), proof.authentication_path.len());
+
+                // Folding with the explicit directions reaches the root verify_proof accepts
+                let root = proof
+                    .authentication_path
+                    .iter()
+                    .rev()
+                    .zip(&directions)
+                    .fold(proof.leaf_hash, |node, (sibling, &on_right)| {
+                        if on_right {
+                            hash_node(&node, sibling)
+                        } else {
+                            hash_node(sibling, &node)
+                        }
+                    });
+                assert_eq!(root, Verifier::compute_root(&proof));
+                assert!(verifier.verify_proof(&proof));
+            }
+        }
+
+        // The promoted last leaf of five skips the two levels without a sibling
+        let prover = Prover::new(&["data0", "data1", "data2", "data3", "data4"], 1).unwrap();
+        assert_eq!(prover.get_proof(4).unwrap().directions(), vec![false]);
+        assert_eq!(
+            prover.get_proof(2).unwrap().directions(),
+            vec![true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_verify_batch_dedup() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        /// Observer counting the fold steps across all proofs.
+        struct StepCounter(Arc<AtomicUsize>);
+
+        impl VerifyObserver for StepCounter {
+            fn on_step(&self, _level: usize) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let steps = Arc::new(AtomicUsize::new(0));
+        let verifier = Verifier::with_observer(prover.root(), StepCounter(Arc::clone(&steps)));
+
+        let unique: Vec<_> = [0, 2, 4]
+            .iter()
+            .map(|&i| prover.get_proof(i).unwrap())
+            .collect();
+        let mut batch: Vec<_> = unique.iter().cycle().take(300).cloned().collect();
+        assert!(verifier.verify_batch_dedup(&batch));
+        assert_eq!(
+            verifier.verify_batch_dedup(&batch),
+            verifier.verify_batch(&batch)
+        );
+        assert!(verifier.verify_batch_dedup(&[]));
+
+        // Each unique proof is folded once
+        let unique_steps: usize = unique
+            .iter()
+            .map(|proof| verifier.verify_counting(proof).1)
+            .sum();
+        let before = steps.load(Ordering::SeqCst);
+        verifier.verify_batch_dedup(&batch);
+        assert_eq!(steps.load(Ordering::SeqCst) - before, unique_steps);
+
+        // A single invalid copy fails the batch, like naive verification
+        let mut tampered = unique[1].clone();
+        tampered.authentication_path[0] = [0u8; 32];
+        batch[150] = tampered;
+        assert!(!verifier.verify_batch_dedup(&batch));
+        assert_eq!(
+            verifier.verify_batch_dedup(&batch),
+            verifier.verify_batch(&batch)
+        );
+    }
+
+    #[test]
+    fn test_compute_root() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.root());
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(Verifier::compute_root(&proof), prover.root());
+            assert!(verifier.verify_proof(&proof));
+
+            let mut tampered = proof.clone();
+            tampered.authentication_path[0][0] ^= 1;
+            assert_ne!(Verifier::compute_root(&tampered), prover.root());
+            assert!(!verifier.verify_proof(&tampered));
+        }
+    }
+
+    #[test]
+    fn test_verify_compact() {
+        for size in 1..=9 {
+            let data: Vec<String> = (0..size).map(|i| format!("data{}", i)).collect();
+            let leaves: Vec<&str> = data.iter().map(String::as_str).collect();
+            let prover = Prover::new(&leaves, 1).expect("Failed to create prover");
+            let verifier = Verifier::new(prover.root());
+
+            for (leaf_index, leaf) in leaves.iter().enumerate() {
+                let proof = prover.get_proof(leaf_index).unwrap();
+                let compact = proof.to_compact();
+                assert_eq!(compact.authentication_path, proof.authentication_path);
+                assert_eq!(
+                    verifier.verify_compact(leaf, &compact),
+                    verifier.verify_proof_for_data(leaf, &proof)
+                );
+                assert!(verifier.verify_compact(leaf, &compact));
+
+                // Other data, or a proof for another position, is rejected like a full proof
+                assert!(!verifier.verify_compact("other", &compact));
+                let mut moved = compact.clone();
+                moved.leaf_index = (leaf_index + 1) % size;
+                let mut moved_full = proof.clone();
+                moved_full.leaf_index = moved.leaf_index;
+                assert_eq!(
+                    verifier.verify_compact(leaf, &moved),
+                    verifier.verify_proof(&moved_full)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_proof_single_leaf() {
+        let prover = Prover::new(&["data0"], 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+        let proof = prover.get_proof(0).unwrap();
+
+        assert!(verifier.verify_proof(&proof));
+        assert_eq!(verifier.verify_counting(&proof), (true, 0));
+
+        // Another leaf, another index, or a non-empty path is rejected
+        let mut other = prover.get_proof(0).unwrap();
+        other.leaf_hash = hash_leaf(b"data1");
+        assert_eq!(
+            verifier.verify_proof_detailed(&other),
+            Err(VerifyError::EmptyPath)
+        );
+        let mut other = prover.get_proof(0).unwrap();
+        other.leaf_index = 1;
+        assert_eq!(
+            verifier.verify_proof_detailed(&other),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+        let mut other = prover.get_proof(0).unwrap();
+        other.authentication_path.push(proof.leaf_hash);
+        assert_eq!(
+            verifier.verify_proof_detailed(&other),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+
+        // A proof for a one-leaf tree does not verify against a larger tree's root
+        let larger = Prover::new(&["data0", "data1"], 1).unwrap();
+        assert!(!Verifier::new(larger.root()).verify_proof(&proof));
+    }
+
+    #[test]
+    fn test_verify_self_contained() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+
+        for leaf_index in 0..data.len() {
+            let bundle = prover.get_self_contained_proof(leaf_index).unwrap();
+            assert_eq!(bundle.root, prover.root());
+            assert!(Verifier::verify_self_contained(&bundle));
+        }
+
+        // Tampering with the root, the leaf, or the path breaks the bundle
+        let bundle = prover.get_self_contained_proof(2).unwrap();
+        let mut tampered = bundle.clone();
+        tampered.root[0] ^= 1;
+        assert!(!Verifier::verify_self_contained(&tampered));
+        let mut tampered = bundle.clone();
+        tampered.proof.leaf_hash = hash_leaf(b"other");
+        assert!(!Verifier::verify_self_contained(&tampered));
+        let mut tampered = bundle.clone();
+        tampered.proof.authentication_path[0] = [0u8; 32];
+        assert!(!Verifier::verify_self_contained(&tampered));
+
+        // A consistent bundle from another tree verifies, so the root must still be trusted
+        let other = Prover::new(&["forged0", "forged1"], 1).unwrap();
+        let forged = other.get_self_contained_proof(0).unwrap();
+        assert!(Verifier::verify_self_contained(&forged));
+        assert!(!Verifier::new(prover.root()).verify_proof(&forged.proof));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        let mut proofs = prover.get_proofs(&[0, 1, 2, 3, 4, 5]).unwrap();
+        assert!(verifier.verify_batch(&proofs));
+        assert!(verifier.verify_batch_parallel(&proofs));
+        assert!(verifier.verify_batch(&[]));
+
+        // A single tampered proof fails the whole batch
+        proofs[3].leaf_hash = hash_leaf(b"tampered");
+        assert!(!verifier.verify_batch(&proofs));
+        assert!(!verifier.verify_batch_parallel(&proofs));
+
+        // Valid proofs from another tree fail too
+        let other_prover = Prover::new(&["other0", "other1"], 1).unwrap();
+        let other_proofs = other_prover.get_proofs(&[0, 1]).unwrap();
+        assert!(!verifier.verify_batch(&other_proofs));
+    }
+
+    #[test]
+    fn test_streaming_verifier() {
+        let data = vec!["data0", "data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let root = prover.get_root_hash().unwrap();
+        let verifier = Verifier::new(root);
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            let mut streaming =
+                StreamingVerifier::new(proof.leaf_hash, proof.leaf_index, proof.tree_size);
+            for sibling in proof.authentication_path.iter().rev() {
+                streaming.push_sibling(*sibling);
+            }
+            assert_eq!(streaming.finish(root), verifier.verify_proof(&proof));
+        }
+
+        let proof = prover.get_proof(4).unwrap();
+        let stream = |leaf_index: usize, siblings: &[[u8; 32]]| {
+            let mut streaming = StreamingVerifier::new(proof.leaf_hash, leaf_index, 6);
+            for sibling in siblings.iter().rev() {
+                streaming.push_sibling(*sibling);
+            }
+            streaming.finish(root)
+        };
+        let path = &proof.authentication_path;
+        assert!(stream(4, path));
+
+        // Wrong index, a missing sibling, an extra sibling, or a wrong root
+        assert!(!stream(5, path));
+        assert!(!stream(6, path));
+        assert!(!stream(4, &path[1..]));
+        let mut extended_path = vec![proof.leaf_hash];
+        extended_path.extend_from_slice(path);
+        assert!(!stream(4, &extended_path));
+        let mut streaming = StreamingVerifier::new(proof.leaf_hash, 4, 6);
+        for sibling in path.iter().rev() {
+            streaming.push_sibling(*sibling);
+        }
+        assert!(!streaming.finish([0u8; 32]));
+
+        // A single leaf is its own root
+        let single = Prover::new(&["data0"], 1).unwrap();
+        let streaming = StreamingVerifier::new(hash_leaf(b"data0"), 0, 1);
+        assert!(streaming.finish(single.root()));
+    }
+
+    #[test]
+    fn test_verify_in_forest() {
+        let trees = [
+            Prover::new(&["a0", "a1", "a2"], 1).unwrap(),
+            Prover::new(&["b0", "b1", "b2", "b3", "b4"], 1).unwrap(),
+            Prover::new(&["c0", "c1"], 1).unwrap(),
+        ];
+        let roots: Vec<[u8; 32]> = trees.iter().map(|t| t.get_root_hash().unwrap()).collect();
+        let roots: Vec<&[u8]> = roots.iter().map(|root| root.as_slice()).collect();
+        let forest = Prover::from_bytes(&roots, 1).expect("Failed to create prover");
+        let super_root = forest.get_root_hash().unwrap();
+
+        let verifier = Verifier::new(trees[1].get_root_hash().unwrap());
+        let forest_proof = forest.get_proof(1).unwrap();
+        for leaf_index in 0..5 {
+            let tree_proof = trees[1].get_proof(leaf_index).unwrap();
+            assert!(verifier.verify_in_forest(super_root, &forest_proof, &tree_proof));
+        }
+
+        let tree_proof = trees[1].get_proof(3).unwrap();
+
+        // The forest proof must be for this verifier's tree
+        let other_forest_proof = forest.get_proof(0).unwrap();
+        assert!(!verifier.verify_in_forest(super_root, &other_forest_proof, &tree_proof));
+
+        // A leaf of another tree in the forest is not in this tree
+        let other_tree_proof = trees[0].get_proof(1).unwrap();
+        assert!(!verifier.verify_in_forest(super_root, &forest_proof, &other_tree_proof));
+
+        // Nor is the tree in a different forest
+        assert!(!verifier.verify_in_forest([0u8; 32], &forest_proof, &tree_proof));
+
+        // A forest of duplicating trees duplicates the last root as well
+        let tree = Prover::builder()
+            .data(&["c0", "c1", "c2"])
+            .pairing(PairingPolicy::Duplicate)
+            .build()
+            .unwrap();
+        let leaves = [roots[0], roots[1], &tree.root()[..]].map(hash_leaf);
+        let super_root = hash_node(
+            &hash_node(&leaves[0], &leaves[1]),
+            &hash_node(&leaves[2], &leaves[2]),
+        );
+        let forest_proof = MerkleProof {
+            leaf_index: 2,
+            tree_size: 3,
+            leaf_hash: leaves[2],
+            authentication_path: vec![hash_node(&leaves[0], &leaves[1]), leaves[2]],
+            leaf_data: None,
+        };
+        let verifier = Verifier::with_pairing(tree.root(), PairingPolicy::Duplicate);
+        let tree_proof = tree.get_proof(2).unwrap();
+        assert!(verifier.verify_in_forest(super_root, &forest_proof, &tree_proof));
+        assert!(!Verifier::new(tree.root()).verify_in_forest(
+            super_root,
+            &forest_proof,
+            &tree_proof
+        ));
+    }
+
+    #[test]
+    fn test_verify_with_index_predicate() {
+        let data = vec!["data0", "parity0", "data1", "parity1"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+        let is_even = |leaf_index: usize| leaf_index.is_multiple_of(2);
+
+        // Valid proofs for even indices are accepted
+        let even_proof = prover.get_proof(2).unwrap();
+        assert!(verifier.verify_with_index_predicate(&even_proof, is_even));
+
+        // Valid proofs for odd indices are rejected
+        let odd_proof = prover.get_proof(3).unwrap();
+        assert!(verifier.verify_proof(&odd_proof));
+        assert!(!verifier.verify_with_index_predicate(&odd_proof, is_even));
+
+        // The predicate does not make an invalid proof valid
+        let wrong_verifier = Verifier::new([0u8; 32]);
+        assert!(!wrong_verifier.verify_with_index_predicate(&even_proof, is_even));
+    }
+
+    #[test]
+    fn test_verify_proof_detailed_valid() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert_eq!(verifier.verify_proof_detailed(&proof), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_verify_proof_detailed_root_mismatch() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        let mut proof = prover.get_proof(1).unwrap();
+        proof.authentication_path[0] = [0u8; 32];
+        assert_eq!(
+            verifier.verify_proof_detailed(&proof),
+            Err(VerifyError::RootMismatch)
+        );
+        assert!(!verifier.verify_proof(&proof));
+    }
+
+    #[test]
+    fn test_verify_proof_detailed_empty_path() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        let mut proof = prover.get_proof(0).unwrap();
+        proof.authentication_path.clear();
+        assert_eq!(
+            verifier.verify_proof_detailed(&proof),
+            Err(VerifyError::EmptyPath)
+        );
+        assert!(!verifier.verify_proof(&proof));
+    }
+
+    #[test]
+    fn test_verify_proof_detailed_index_path_length_mismatch() {
+        let data = vec!["data1", "data2", "data3", "data4"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        // Index 4 needs a path of at least 3 hashes
+        let mut proof = prover.get_proof(0).unwrap();
+        proof.leaf_index = 4;
+        assert_eq!(
+            verifier.verify_proof_detailed(&proof),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+        assert!(!verifier.verify_proof(&proof));
+    }
+
+    #[test]
+    fn test_verify_with_receipt() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let root_hash = prover.get_root_hash().unwrap();
+        let verifier = Verifier::new(root_hash);
+
+        let before = SystemTime::now();
+        let mut proof = prover.get_proof(3).unwrap();
+        let receipt = verifier.verify_with_receipt(&proof);
+        assert_eq!(receipt.root, root_hash);
+        assert_eq!(receipt.leaf_hash, proof.leaf_hash);
+        assert_eq!(receipt.leaf_index, 3);
+        assert!(receipt.verified);
+        assert!(receipt.timestamp >= before);
+
+        proof.leaf_hash = [0u8; 32];
+        let receipt = verifier.verify_with_receipt(&proof);
+        assert_eq!(receipt.leaf_hash, [0u8; 32]);
+        assert!(!receipt.verified);
+    }
+
+    /// Expands run-length encoded data made of (count, byte) pairs.
+    fn run_length_decode(encoded: &[u8]) -> Vec<u8> {
+        encoded
+            .chunks_exact(2)
+            .flat_map(|run| std::iter::repeat_n(run[1], run[0] as usize))
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_data_decoded() {
+        let compressed: [&[u8]; 3] = [&[3, b'a', 2, b'b'], &[1, b'c'], &[4, b'd']];
+        let decompressed = ["aaabb", "c", "dddd"];
+
+        let reference = Prover::new(&decompressed, 1).expect("Failed to create prover");
+        let prover = Prover::with_decode(&compressed, 1, run_length_decode)
+            .expect("Failed to create prover");
+        let root_hash = reference.get_root_hash().unwrap();
+        assert_eq!(prover.get_root_hash().unwrap(), root_hash);
+
+        let verifier = Verifier::new(root_hash);
+        for (leaf_index, leaf) in compressed.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert!(verifier.verify_data_decoded(leaf, run_length_decode, &proof));
+        }
+
+        // Compressed data for another leaf does not match the proof
+        let proof = prover.get_proof(0).unwrap();
+        assert!(!verifier.verify_data_decoded(compressed[1], run_length_decode, &proof));
+    }
+
+    #[test]
+    fn test_verify_proof_for_data() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        for (leaf_index, leaf) in data.iter().enumerate() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            assert!(verifier.verify_proof_for_data(leaf, &proof));
+        }
+
+        // A valid proof for another leaf does not prove the data
+        let proof = prover.get_proof(1).unwrap();
+        assert!(!verifier.verify_proof_for_data("data1", &proof));
+
+        // Nor does a leaf hash swapped for the hash of the data
+        let mut tampered = prover.get_proof(1).unwrap();
+        tampered.leaf_hash = hash_leaf(b"data1");
+        assert!(!verifier.verify_proof_for_data("data1", &tampered));
+    }
+
+    #[test]
+    fn test_verify_value_not_at() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+        let proof = prover.get_proof(2).unwrap();
+
+        // The leaf differs from the claimed value
+        assert!(verifier.verify_value_not_at(2, "data4", &proof));
+
+        // The leaf holds the value
+        assert!(!verifier.verify_value_not_at(2, "data3", &proof));
+
+        // The proof must be for the index in question and valid
+        assert!(!verifier.verify_value_not_at(3, "data3", &proof));
+        assert!(!Verifier::new([0u8; 32]).verify_value_not_at(2, "data4", &proof));
+    }
+
+    #[test]
+    fn test_verify_proof_with_meta() {
+        let data = vec!["data1", "data2", "data3", "data4", "data5"];
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+        let proof = prover.get_proof(1).unwrap();
+
+        let meta = b"schema=1;timestamp=1700000000";
+        let other_meta = b"schema=2;timestamp=1700000000";
+        let commitment = prover.committed_root_with_meta(meta);
+        assert_ne!(commitment, prover.committed_root_with_meta(other_meta));
+        assert_ne!(commitment, prover.get_root_hash().unwrap());
+
+        assert!(verifier.verify_proof_with_meta(&proof, meta, commitment));
+        assert!(!verifier.verify_proof_with_meta(&proof, other_meta, commitment));
+
+        // The commitment does not make an invalid proof valid
+        let mut wrong_proof = prover.get_proof(1).unwrap();
+        wrong_proof.leaf_hash = [0u8; 32];
+        assert!(!verifier.verify_proof_with_meta(&wrong_proof, meta, commitment));
+    }
+
+    #[test]
+    fn test_verify_consistency_all_sizes() {
+        let data: Vec<String> = (0..16).map(|i| format!("data{}", i)).collect();
+        let data: Vec<&str> = data.iter().map(AsRef::as_ref).collect();
+        let roots: Vec<[u8; 32]> = (1..=data.len())
+            .map(|size| {
+                Prover::new(&data[..size], 1)
+                    .unwrap()
+                    .get_root_hash()
+                    .unwrap()
+            })
+            .collect();
+        let verifier = Verifier::new(roots[data.len() - 1]);
+
+        for new_size in 1..=data.len() {
+            let prover = Prover::new(&data[..new_size], 1).expect("Failed to create prover");
+            for old_size in 1..=new_size {
+                let proof = prover.consistency_proof(old_size).unwrap();
+                let (old_root, new_root) = (roots[old_size - 1], roots[new_size - 1]);
+                assert!(
+                    verifier.verify_consistency(old_root, new_root, &proof),
+                    "Failed for old_size {} and new_size {}",
+                    old_size,
+                    new_size
+                );
+
+                // Swapping the roots must fail unless they are the same tree
+                assert_eq!(
+                    verifier.verify_consistency(new_root, old_root, &proof),
+                    old_size == new_size
+                );
+            }
+            assert!(prover.consistency_proof(0).is_err());
+            assert!(prover.consistency_proof(new_size + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_promoted_leaf_paths() {
+        // In a tree of 11 leaves, leaves 8..=10 lie below the promoted right subtree
+        let data: Vec<String> = (0..11).map(|i| format!("data{}", i)).collect();
+        let data: Vec<&str> = data.iter().map(AsRef::as_ref).collect();
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        for leaf_index in 0..data.len() {
+            let proof = prover.get_proof(leaf_index).unwrap();
+            let expected_length = match leaf_index {
+                0..=7 => 4,
+                8 | 9 => 3,
+                _ => 2,
+            };
+            assert_eq!(proof.authentication_path.len(), expected_length);
+            assert_eq!(verifier.verify_proof_detailed(&proof), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_paths_not_matching_tree_size() {
+        let data: Vec<String> = (0..11).map(|i| format!("data{}", i)).collect();
+        let data: Vec<&str> = data.iter().map(AsRef::as_ref).collect();
+        let prover = Prover::new(&data, 1).expect("Failed to create prover");
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        // A promoted leaf padded to the full height, as if its node had been duplicated
+        let mut promoted = prover.get_proof(10).unwrap();
+        let leaf_hash = promoted.leaf_hash;
+        promoted.authentication_path.push(leaf_hash);
+        assert_eq!(
+            verifier.verify_proof_detailed(&promoted),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+
+        // A leaf outside the promoted subtree with a shortened path
+        let mut full = prover.get_proof(3).unwrap();
+        full.authentication_path.remove(0);
+        assert_eq!(
+            verifier.verify_proof_detailed(&full),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+
+        // The leaf index must lie inside the claimed tree
+        let mut resized = prover.get_proof(8).unwrap();
+        resized.tree_size = 8;
+        assert_eq!(
+            verifier.verify_proof_detailed(&resized),
+            Err(VerifyError::IndexPathLengthMismatch)
+        );
+    }
+}
+
+/// Verification without the `std` feature, where no `Prover` is available to build trees.
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use super::*;
+    use crate::hasher::{hash_leaf, hash_node};
+    use alloc::vec;
+
+    #[test]
+    fn test_verify_decoded_proof_without_std() {
+        let leaves = [
+            hash_leaf(b"data1"),
+            hash_leaf(b"data2"),
+            hash_leaf(b"data3"),
+        ];
+        let root = hash_node(&hash_node(&leaves[0], &leaves[1]), &leaves[2]);
+        let verifier = Verifier::new(root);
+
+        let proof = MerkleProof {
+            leaf_index: 1,
+            tree_size: 3,
+            leaf_hash: leaves[1],
+            authentication_path: vec![leaves[2], leaves[0]],
+            leaf_data: None,
+        };
+        let decoded = MerkleProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(verifier.verify_proof(&decoded));
+        assert!(verifier.verify_proof_for_data("data2", &decoded));
+
+        // The promoted leaf has a single sibling
+        let promoted = MerkleProof {
+            leaf_index: 2,
+            tree_size: 3,
+            leaf_hash: leaves[2],
+            authentication_path: vec![hash_node(&leaves[0], &leaves[1])],
+            leaf_data: None,
+        };
+        let decoded = MerkleProof::from_tlv(&promoted.to_tlv()).unwrap();
+        assert_eq!(verifier.verify_proof_detailed(&decoded), Ok(()));
+
+        let mut forged = decoded;
+        forged.leaf_hash = leaves[1];
+        assert!(!verifier.verify_proof(&forged));
+    }
 }
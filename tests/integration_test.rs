@@ -1,5 +1,8 @@
 use merkletree::Prover;
 use merkletree::Verifier;
+use merkletree::{hash_data_sequences, hash_leaf, hash_node};
+use merkletree::{CellProof, MerkleProof};
+use merkletree::{Hasher, MerkleProver, MerkleVerifier};
 use std::fs;
 
 #[test]
@@ -76,6 +79,78 @@ fn thread_numbers_and_leaf_indices_systematically() {
     }
 }
 
+#[test]
+fn build_with_verifier_protocol() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+        "integration04",
+    ];
+
+    let num_threads = 2;
+
+    let (prover, verifier) = match Prover::build_with_verifier(&data, num_threads) {
+        Ok(pair) => pair,
+        Err(e) => panic!("Failed to create Prover and Verifier instances: {}", e),
+    };
+
+    for leaf_index in 0..data.len() {
+        let proof = match prover.get_proof(leaf_index) {
+            Ok(p) => p,
+            Err(e) => panic!("Failed to get proof for leaf index {}: {}", leaf_index, e),
+        };
+        assert!(verifier.verify_proof(&proof));
+    }
+
+    assert!(Prover::build_with_verifier(&[], num_threads).is_err());
+}
+
+#[test]
+fn consistency_proof_protocol() {
+    let data = [
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+        "integration04",
+        "integration05",
+        "integration06",
+    ];
+
+    let num_threads = 1;
+
+    let mut prover = match Prover::new(&data[..4], num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let old_root = prover.get_root_hash().unwrap();
+
+    // Grow the log from 4 to 7 leaves
+    if let Err(e) = prover.append(&data[4..]) {
+        panic!("Failed to append to Prover instance: {}", e);
+    }
+    let new_root = prover.get_root_hash().unwrap();
+
+    let proof = match prover.consistency_proof(4) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get consistency proof: {}", e),
+    };
+    assert_eq!((proof.old_size, proof.new_size), (4, 7));
+
+    let verifier = Verifier::new(new_root);
+    assert!(verifier.verify_consistency(old_root, new_root, &proof));
+
+    // A different old tree is not consistent with the new one
+    let other_prover = match Prover::new(&["other00", "other01", "other02", "other03"], 1) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let other_root = other_prover.get_root_hash().unwrap();
+    assert!(!verifier.verify_consistency(other_root, new_root, &proof));
+}
+
 #[test]
 fn wrong_proof() {
     let data1 = vec![
@@ -125,3 +200,642 @@ fn wrong_proof() {
     // Should not be valid.
     assert!(!verifier.verify_proof(&proof2));
 }
+
+#[test]
+fn compact_range_matches_prefix_root() {
+    // Read data from file
+    let content = fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+
+    let num_threads = 4;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+
+    for k in [1, 2, 3, 5, 6, 7, 8, 100, 511, 512, 513, 999, 1000] {
+        let range = match prover.compact_range(k) {
+            Ok(r) => r,
+            Err(e) => panic!("Failed to get compact range for k = {}: {}", k, e),
+        };
+        assert_eq!(range.len(), k.count_ones() as usize);
+
+        // Build a tree over the prefix only and compare its root to the combined range
+        let prefix_prover = match Prover::new(&data[..k], num_threads) {
+            Ok(p) => p,
+            Err(e) => panic!("Failed to create Prover instance: {}", e),
+        };
+        let prefix_root = prefix_prover.get_root_hash().unwrap();
+
+        let combined_root = match Verifier::compact_range_root(k, &range) {
+            Ok(hash) => hash,
+            Err(e) => panic!("Failed to combine compact range for k = {}: {}", k, e),
+        };
+        assert_eq!(
+            combined_root, prefix_root,
+            "Prefix root mismatch for k = {}",
+            k
+        );
+    }
+
+    // Prefixes outside the tree cannot be covered
+    assert!(prover.compact_range(0).is_err());
+    assert!(prover.compact_range(data.len() + 1).is_err());
+}
+
+#[test]
+fn proof_verifies_against_agrees_with_verifier() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+        "integration04",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let root_hash = prover.get_root_hash().unwrap();
+    let wrong_root_hash = [0u8; 32];
+
+    let verifier = Verifier::new(root_hash);
+    let wrong_verifier = Verifier::new(wrong_root_hash);
+
+    for leaf_index in 0..data.len() {
+        let proof = prover.get_proof(leaf_index).unwrap();
+
+        // Matching root
+        assert!(proof.verifies_against(root_hash));
+        assert_eq!(
+            proof.verifies_against(root_hash),
+            verifier.verify_proof(&proof)
+        );
+
+        // Mismatching root
+        assert!(!proof.verifies_against(wrong_root_hash));
+        assert_eq!(
+            proof.verifies_against(wrong_root_hash),
+            wrong_verifier.verify_proof(&proof)
+        );
+    }
+}
+
+#[test]
+fn internal_node_proof_is_flagged_as_potentially_forged() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let root_hash = prover.get_root_hash().unwrap();
+    let verifier = Verifier::new(root_hash);
+
+    // Genuine proofs have a full length path and are not flagged
+    for leaf_index in 0..data.len() {
+        let proof = prover.get_proof(leaf_index).unwrap();
+        assert!(!proof.is_potentially_forged(data.len()));
+    }
+
+    // Present the left internal node as if it were leaf 0 of a tree of height 1
+    let proof0 = prover.get_proof(0).unwrap();
+    let proof1 = prover.get_proof(1).unwrap();
+    let forged_proof = MerkleProof {
+        leaf_index: 0,
+        tree_size: 2,
+        leaf_hash: hash_node(&proof0.leaf_hash, &proof1.leaf_hash),
+        authentication_path: vec![proof0.authentication_path[0]],
+        leaf_data: None,
+    };
+
+    // A bare internal node hash still folds to the root, but the proof is flagged
+    assert!(verifier.verify_proof(&forged_proof));
+    assert!(forged_proof.is_potentially_forged(data.len()));
+}
+
+#[test]
+fn duplicated_last_leaf_does_not_collide() {
+    // Duplicating the last node of odd levels made these two vectors share a root
+    let data1 = vec!["integration00", "integration01", "integration02"];
+    let data2 = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration02",
+    ];
+
+    let num_threads = 1;
+
+    let prover1 = match Prover::new(&data1, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let prover2 = match Prover::new(&data2, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let root_hash1 = prover1.get_root_hash().unwrap();
+    let root_hash2 = prover2.get_root_hash().unwrap();
+    assert_ne!(root_hash1, root_hash2);
+
+    // A proof for the duplicated leaf of the longer vector does not verify against the shorter one
+    let proof = match prover2.get_proof(3) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get proof for leaf index 3: {}", e),
+    };
+    assert!(Verifier::new(root_hash2).verify_proof(&proof));
+    assert!(!Verifier::new(root_hash1).verify_proof(&proof));
+
+    // The promoted last leaf of the shorter vector has a shorter path and still verifies
+    let proof = match prover1.get_proof(2) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get proof for leaf index 2: {}", e),
+    };
+    assert_eq!(proof.authentication_path.len(), 1);
+    assert!(Verifier::new(root_hash1).verify_proof(&proof));
+}
+
+#[test]
+fn empty_leaves_are_distinct_from_absent_leaves() {
+    let with_empty = vec!["integration00", "", "integration02"];
+    let without_empty = vec!["integration00", "integration02"];
+
+    let prover = Prover::new(&with_empty, 1).expect("Failed to create Prover instance");
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+    for (leaf_index, leaf) in with_empty.iter().enumerate() {
+        let proof = prover.get_proof(leaf_index).unwrap();
+        assert!(verifier.verify_proof_for_data(leaf, &proof));
+    }
+
+    // Dropping the empty leaf changes the root, and its proof does not verify there
+    let other = Prover::new(&without_empty, 1).expect("Failed to create Prover instance");
+    assert_ne!(prover.get_root_hash(), other.get_root_hash());
+    let proof = prover.get_proof(1).unwrap();
+    assert!(!Verifier::new(other.get_root_hash().unwrap()).verify_proof(&proof));
+
+    // Nor does an empty leaf prove that the leaf at its position holds other data
+    assert!(!verifier.verify_proof_for_data("integration02", &proof));
+}
+
+#[test]
+fn inline_proof_self_verifies() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new_retaining(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    let leaf_index = 2;
+    let mut proof = match prover.get_proof_inline(leaf_index) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get proof for leaf index {}: {}", leaf_index, e),
+    };
+    assert!(verifier.verify_inline_proof(&proof));
+
+    // Tampering with the embedded data must be detected
+    proof.leaf_data = Some(b"tampered".to_vec());
+    assert!(!verifier.verify_inline_proof(&proof));
+
+    // A proof without embedded data is not an inline proof
+    proof.leaf_data = None;
+    assert!(!verifier.verify_inline_proof(&proof));
+}
+
+#[test]
+fn concatenated_child_hashes_are_not_a_valid_leaf() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    // Forge leaf data equal to the concatenation of the two children of the left internal node
+    let proof0 = prover.get_proof(0).unwrap();
+    let proof1 = prover.get_proof(1).unwrap();
+    let forged_data = [proof0.leaf_hash, proof1.leaf_hash].concat();
+
+    // Claim the forged data is leaf 0 of a tree of height 1
+    let forged_proof = MerkleProof {
+        leaf_index: 0,
+        tree_size: 2,
+        leaf_hash: hash_leaf(&forged_data),
+        authentication_path: vec![proof0.authentication_path[0]],
+        leaf_data: Some(forged_data),
+    };
+
+    // The leaf prefix keeps the forged leaf from colliding with the internal node
+    assert!(!verifier.verify_proof(&forged_proof));
+    assert!(!verifier.verify_inline_proof(&forged_proof));
+}
+
+/// SHA256 applied twice, standing in for an alternative digest.
+struct DoubleSha256Hasher;
+
+impl Hasher for DoubleSha256Hasher {
+    fn hash(datas: &[&[u8]]) -> [u8; 32] {
+        hash_data_sequences(&[&hash_data_sequences(datas)])
+    }
+}
+
+#[test]
+fn custom_hasher_changes_root_and_verifies() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+        "integration04",
+    ];
+
+    let num_threads = 1;
+
+    let sha256_prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let custom_prover = match MerkleProver::<DoubleSha256Hasher>::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+
+    let sha256_root = sha256_prover.get_root_hash().unwrap();
+    let custom_root = custom_prover.get_root_hash().unwrap();
+    assert_ne!(
+        sha256_root, custom_root,
+        "Different hashers must yield different roots"
+    );
+
+    // Proofs only verify with the hasher the tree was built with
+    let custom_verifier = MerkleVerifier::<DoubleSha256Hasher>::new(custom_root);
+    let sha256_verifier = Verifier::new(custom_root);
+    for leaf_index in 0..data.len() {
+        let proof = custom_prover.get_proof(leaf_index).unwrap();
+        assert!(custom_verifier.verify_proof(&proof));
+        assert!(!sha256_verifier.verify_proof(&proof));
+    }
+}
+
+#[test]
+fn binary_data_protocol() {
+    // Leaves that are not valid UTF-8
+    let data: Vec<&[u8]> = vec![
+        &[0xff, 0xfe, 0x00],
+        &[0x80],
+        &[0xc3, 0x28],
+        &[],
+        &[0x00, 0x01, 0x02, 0x03],
+    ];
+    assert!(data.iter().any(|d| std::str::from_utf8(d).is_err()));
+
+    let num_threads = 2;
+
+    let prover = match Prover::from_bytes(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    for (leaf_index, leaf) in data.iter().enumerate() {
+        let proof = match prover.get_proof(leaf_index) {
+            Ok(p) => p,
+            Err(e) => panic!("Failed to get proof for leaf index {}: {}", leaf_index, e),
+        };
+        assert_eq!(proof.leaf_hash, hash_leaf(leaf));
+        assert!(verifier.verify_proof(&proof));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_json_round_trip() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    let leaf_index = 1;
+    let proof = prover.get_proof(leaf_index).unwrap();
+
+    let json = serde_json::to_string(&proof).expect("Failed to serialize proof");
+    let decoded: MerkleProof = serde_json::from_str(&json).expect("Failed to deserialize proof");
+
+    assert_eq!(decoded.leaf_index, proof.leaf_index);
+    assert_eq!(decoded.leaf_hash, proof.leaf_hash);
+    assert_eq!(decoded.authentication_path, proof.authentication_path);
+    assert!(verifier.verify_proof(&decoded));
+}
+
+#[test]
+fn matrix_cell_protocol() {
+    let rows: Vec<&[&str]> = vec![
+        &["cell00", "cell01", "cell02", "cell03"],
+        &["cell10", "cell11", "cell12", "cell13"],
+        &["cell20", "cell21", "cell22", "cell23"],
+    ];
+
+    let num_threads = 2;
+
+    let matrix = match Prover::matrix(&rows, num_threads) {
+        Ok(m) => m,
+        Err(e) => panic!("Failed to create matrix commitment: {}", e),
+    };
+    let root_hash = matrix.get_root_hash().unwrap();
+    let verifier = Verifier::new(root_hash);
+
+    let (row, column) = (1, 2);
+    let proof = match matrix.get_cell_proof(row, column) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get proof for cell ({}, {}): {}", row, column, e),
+    };
+    assert_eq!(proof.cell_proof.leaf_index, column);
+    assert_eq!(proof.row_proof.leaf_index, row);
+    assert_eq!(
+        proof.cell_proof.leaf_hash,
+        hash_leaf(rows[row][column].as_bytes())
+    );
+
+    // Fold the cell proof to its row root, then the row proof to the matrix root
+    let row_prover = Prover::new(rows[row], num_threads).unwrap();
+    let row_root = row_prover.get_root_hash().unwrap();
+    assert!(proof.cell_proof.verifies_against(row_root));
+    assert_eq!(proof.row_proof.leaf_hash, hash_leaf(&row_root));
+    assert!(proof.row_proof.verifies_against(root_hash));
+    assert!(verifier.verify_cell_proof(&proof));
+
+    // A cell from another row does not verify with this row's proof
+    let other_proof = matrix.get_cell_proof(0, column).unwrap();
+    let mixed_proof = CellProof {
+        cell_proof: other_proof.cell_proof,
+        row_proof: proof.row_proof,
+    };
+    assert!(!verifier.verify_cell_proof(&mixed_proof));
+
+    assert!(matrix.get_cell_proof(rows.len(), 0).is_err());
+    assert!(matrix.get_cell_proof(0, rows[0].len()).is_err());
+}
+
+#[test]
+fn multiproof_protocol() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+        "integration04",
+        "integration05",
+        "integration06",
+        "integration07",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    let leaf_indices = [5, 0, 1];
+    let multiproof = match prover.get_multiproof(&leaf_indices) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to get multiproof: {}", e),
+    };
+    assert_eq!(multiproof.leaf_indices, vec![0, 1, 5]);
+    assert!(verifier.verify_multiproof(&multiproof));
+
+    // The multiproof holds fewer hashes than the individual proofs together
+    let multiproof_hashes = multiproof.leaf_hashes.len() + multiproof.sibling_hashes.len();
+    let individual_hashes: usize = leaf_indices
+        .iter()
+        .map(|&leaf_index| {
+            1 + prover
+                .get_proof(leaf_index)
+                .unwrap()
+                .authentication_path
+                .len()
+        })
+        .sum();
+    assert!(
+        multiproof_hashes < individual_hashes,
+        "Multiproof has {} hashes, individual proofs {}",
+        multiproof_hashes,
+        individual_hashes
+    );
+
+    // Tampering with a leaf hash, a sibling hash, or an index invalidates the proof
+    let mut tampered = prover.get_multiproof(&leaf_indices).unwrap();
+    tampered.leaf_hashes[2] = hash_leaf(b"modified");
+    assert!(!verifier.verify_multiproof(&tampered));
+
+    let mut tampered = prover.get_multiproof(&leaf_indices).unwrap();
+    tampered.sibling_hashes[0] = [0u8; 32];
+    assert!(!verifier.verify_multiproof(&tampered));
+
+    let mut tampered = prover.get_multiproof(&leaf_indices).unwrap();
+    tampered.leaf_indices[2] = 4;
+    assert!(!verifier.verify_multiproof(&tampered));
+
+    assert!(prover.get_multiproof(&[]).is_err());
+    assert!(prover.get_multiproof(&[0, data.len()]).is_err());
+}
+
+#[test]
+fn multiproof_non_complete_tree() {
+    // Read data from file
+    let content = fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+
+    let prover = Prover::new(&data, 4).unwrap();
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    for leaf_indices in [
+        vec![999],
+        vec![0, 999],
+        vec![3, 4, 500, 998],
+        (0..1000).collect(),
+    ] {
+        let multiproof = prover.get_multiproof(&leaf_indices).unwrap();
+        assert!(
+            verifier.verify_multiproof(&multiproof),
+            "Failed for leaf indices {:?}",
+            &leaf_indices[..leaf_indices.len().min(4)]
+        );
+    }
+}
+
+#[test]
+fn range_proof_protocol() {
+    // Read data from file
+    let content = fs::read_to_string("tests/data/data1000.txt").expect("Failed to read the file");
+    let data: Vec<&str> = content.lines().collect();
+
+    for size in [1, 5, 6, 8, 1000] {
+        let prover = Prover::new(&data[..size], 1).unwrap();
+        let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+        // Ranges in the middle, at the start, at the end, and the full range
+        let ranges = [
+            (size / 3, (2 * size / 3).max(size / 3 + 1)),
+            (0, size.div_ceil(2)),
+            (size - 1, size),
+            (0, size),
+        ];
+        for (start, end) in ranges {
+            let proof = prover.range_proof(start, end).unwrap();
+            assert_eq!(proof.leaf_hashes.len(), end - start);
+            assert!(
+                verifier.verify_range(&proof),
+                "Failed for range [{}, {}) of {} leaves",
+                start,
+                end,
+                size
+            );
+        }
+    }
+
+    let prover = Prover::new(&data[..1000], 1).unwrap();
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    // Only the boundary siblings are included
+    let proof = prover.range_proof(300, 700).unwrap();
+    assert!(proof.sibling_hashes.len() <= 2 * prover.height());
+
+    // The full range needs no siblings
+    assert!(prover
+        .range_proof(0, 1000)
+        .unwrap()
+        .sibling_hashes
+        .is_empty());
+
+    // Tampering with a leaf, a sibling, the start, or the number of siblings fails
+    let mut tampered = prover.range_proof(300, 700).unwrap();
+    tampered.leaf_hashes[200] = hash_leaf(b"modified");
+    assert!(!verifier.verify_range(&tampered));
+
+    let mut tampered = prover.range_proof(300, 700).unwrap();
+    tampered.sibling_hashes[0] = [0u8; 32];
+    assert!(!verifier.verify_range(&tampered));
+
+    let mut tampered = prover.range_proof(300, 700).unwrap();
+    tampered.start = 301;
+    assert!(!verifier.verify_range(&tampered));
+
+    let mut tampered = prover.range_proof(300, 700).unwrap();
+    tampered.sibling_hashes.pop();
+    assert!(!verifier.verify_range(&tampered));
+
+    let mut tampered = prover.range_proof(300, 700).unwrap();
+    tampered.sibling_hashes.push([0u8; 32]);
+    assert!(!verifier.verify_range(&tampered));
+
+    // Empty and out-of-bounds ranges
+    assert!(prover.range_proof(10, 10).is_err());
+    assert!(prover.range_proof(10, 5).is_err());
+    assert!(prover.range_proof(990, 1001).is_err());
+}
+
+#[test]
+fn leaf_index_beyond_path_capacity_is_rejected() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+
+    let prover = match Prover::new(&data, num_threads) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    // A genuine proof for leaf 1 with a path of length 2
+    let mut proof = prover.get_proof(1).unwrap();
+    assert!(verifier.verify_proof(&proof));
+
+    // Setting bits above the path length leaves the hashing order unchanged,
+    // but the index no longer fits a tree of height 2
+    for crafted_index in [1 + 4, 1 + 8, 1 + (1 << 40)] {
+        proof.leaf_index = crafted_index;
+        assert!(
+            !verifier.verify_proof(&proof),
+            "Leaf index {} should be rejected",
+            crafted_index
+        );
+    }
+}
+
+#[test]
+fn sequenced_proof_is_not_replayable() {
+    let data = vec![
+        "integration00",
+        "integration01",
+        "integration02",
+        "integration03",
+    ];
+
+    let num_threads = 1;
+    let seq = 41;
+
+    let prover = match Prover::new_sequenced(&data, num_threads, seq) {
+        Ok(p) => p,
+        Err(e) => panic!("Failed to create Prover instance: {}", e),
+    };
+    let verifier = Verifier::new(prover.get_root_hash().unwrap());
+
+    let leaf_index = 2;
+    let proof = prover.get_proof(leaf_index).unwrap();
+
+    // The proof is accepted for its own sequence number only
+    assert!(verifier.verify_sequenced(data[leaf_index], seq, &proof));
+    assert!(!verifier.verify_sequenced(data[leaf_index], seq + 1, &proof));
+
+    // The sequence number changes the commitment
+    let unsequenced = Prover::new(&data, num_threads).unwrap();
+    assert_ne!(
+        unsequenced.get_root_hash().unwrap(),
+        prover.get_root_hash().unwrap()
+    );
+}